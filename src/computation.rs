@@ -0,0 +1,312 @@
+//! Incremental Keccak-prime computation.
+//!
+//! With a large `delay` and number of VDF iterations, a single Keccak-prime evaluation can take
+//! minutes. [`PrimeComputation`] splits the Sloth chain into steps which can be run in chunks,
+//! and its state can be serialized to resume the computation later with an identical output.
+
+use std::convert::TryInto;
+
+use num_bigint::BigUint;
+
+use crate::{
+    expansion::{expand, INPUT_HASH_SIZE, NONCE_SIZE},
+    keccak::Keccak,
+    params::{ExpansionKdf, PrimeParams},
+    prime::{finalize, KeccakPrimeError},
+    sloth::{self, SlothParams},
+    vdf::VdfKind,
+};
+
+/// Identifies serialized checkpoints.
+const CHECKPOINT_MAGIC: &[u8; 4] = b"KPC\x02";
+
+/// Resumable Keccak-prime computation.
+///
+/// The chain of `vdf_iterations` Sloth solutions with a `delay` each is equivalent to a single
+/// chain of `delay * vdf_iterations` Sloth steps, which are executed with [`step`].
+///
+/// [`step`]: PrimeComputation::step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimeComputation {
+    prev_hash: [u8; INPUT_HASH_SIZE],
+    root_hash: [u8; INPUT_HASH_SIZE],
+    nonce: [u8; NONCE_SIZE],
+    params: PrimeParams,
+    /// Number of Sloth steps performed so far.
+    steps_done: u64,
+    /// Current witness of the Sloth chain.
+    witness: BigUint,
+}
+
+impl PrimeComputation {
+    /// Expands the block and prepares the Sloth chain.
+    ///
+    /// Only [`VdfKind::Sloth`] can be split into steps, other VDFs are rejected.
+    pub fn new(
+        prev_hash: [u8; INPUT_HASH_SIZE],
+        root_hash: [u8; INPUT_HASH_SIZE],
+        nonce: [u8; NONCE_SIZE],
+        params: PrimeParams,
+    ) -> Result<PrimeComputation, KeccakPrimeError> {
+        if params.vdf() != VdfKind::Sloth {
+            return Err(KeccakPrimeError::UnsupportedVdf(params.vdf()));
+        }
+        let block = expand(
+            prev_hash,
+            root_hash,
+            nonce,
+            params.expansion_size(),
+            params.kdf(),
+        )?;
+
+        Ok(PrimeComputation {
+            prev_hash,
+            root_hash,
+            nonce,
+            params,
+            steps_done: 0,
+            witness: BigUint::from_bytes_be(&block),
+        })
+    }
+
+    /// Parameters of the computation.
+    pub fn params(&self) -> &PrimeParams {
+        &self.params
+    }
+
+    /// Number of Sloth steps performed so far.
+    pub fn steps_done(&self) -> u64 {
+        self.steps_done
+    }
+
+    /// Total number of Sloth steps required to finish the computation.
+    pub fn total_steps(&self) -> u64 {
+        self.params
+            .delay()
+            .saturating_mul(self.params.vdf_iterations() as u64)
+    }
+
+    /// Returns `true` if all Sloth steps have been performed.
+    pub fn is_finished(&self) -> bool {
+        self.steps_done >= self.total_steps()
+    }
+
+    /// Performs up to `n` Sloth steps.
+    ///
+    /// ## Returns
+    /// - The number of steps actually performed.
+    pub fn step(&mut self, n: u64) -> u64 {
+        let n = n.min(self.total_steps() - self.steps_done);
+        if n > 0 {
+            self.witness = sloth::solve(self.witness.clone(), n)
+                .expect("witness is checked to be below the modulus");
+            self.steps_done += n;
+        }
+        n
+    }
+
+    /// Returns the Keccak-prime output if the computation is finished.
+    pub fn output(&self) -> Option<Vec<u8>> {
+        if !self.is_finished() {
+            return None;
+        }
+        let keccak = Keccak::with_rate_capacity(self.params.rate(), self.params.capacity());
+        Some(finalize(keccak, &self.witness, &self.params))
+    }
+
+    /// Performs all remaining Sloth steps and returns the Keccak-prime output.
+    pub fn finish(mut self) -> Vec<u8> {
+        self.step(u64::MAX);
+        self.output()
+            .expect("all steps have been performed by the previous call")
+    }
+
+    /// Serializes the computation state into a checkpoint.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let witness = self.witness.to_bytes_be();
+
+        let mut bytes = Vec::with_capacity(128 + witness.len());
+        bytes.extend_from_slice(CHECKPOINT_MAGIC);
+        bytes.extend_from_slice(&self.prev_hash);
+        bytes.extend_from_slice(&self.root_hash);
+        bytes.extend_from_slice(&self.nonce);
+        for value in [
+            self.params.expansion_size() as u64,
+            self.params.rate() as u64,
+            self.params.output_len() as u64,
+            self.params.penalty() as u64,
+            self.params.delay(),
+            self.params.vdf_iterations() as u64,
+            self.params.kdf() as u64,
+            self.steps_done,
+            witness.len() as u64,
+        ] {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        bytes.extend_from_slice(&witness);
+        bytes
+    }
+
+    /// Restores the computation state from a checkpoint created with [`to_bytes`].
+    ///
+    /// [`to_bytes`]: PrimeComputation::to_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<PrimeComputation, KeccakPrimeError> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(CHECKPOINT_MAGIC.len())? != CHECKPOINT_MAGIC {
+            return Err(KeccakPrimeError::MalformedCheckpoint);
+        }
+        let prev_hash = reader.array()?;
+        let root_hash = reader.array()?;
+        let nonce = reader.array()?;
+
+        let params = PrimeParams::builder()
+            .expansion_size(reader.usize()?)
+            .rate(reader.usize()?)
+            .output_len(reader.usize()?)
+            .penalty(reader.usize()?)
+            .delay(reader.u64()?)
+            .vdf_iterations(reader.usize()?)
+            .kdf(match reader.u64()? {
+                0 => ExpansionKdf::Xor,
+                #[cfg(feature = "cshake")]
+                1 => ExpansionKdf::CShake256,
+                _ => return Err(KeccakPrimeError::MalformedCheckpoint),
+            })
+            .build()?;
+
+        let steps_done = reader.u64()?;
+        let witness_len = reader.usize()?;
+        let witness = BigUint::from_bytes_be(reader.take(witness_len)?);
+
+        if !reader.bytes.is_empty() {
+            return Err(KeccakPrimeError::MalformedCheckpoint);
+        }
+
+        let computation = PrimeComputation {
+            prev_hash,
+            root_hash,
+            nonce,
+            params,
+            steps_done,
+            witness,
+        };
+        if computation.steps_done > computation.total_steps()
+            || computation.witness >= *SlothParams::default().modulus()
+        {
+            return Err(KeccakPrimeError::MalformedCheckpoint);
+        }
+        Ok(computation)
+    }
+}
+
+/// Reads consecutive fields from a checkpoint.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], KeccakPrimeError> {
+        if self.bytes.len() < len {
+            return Err(KeccakPrimeError::MalformedCheckpoint);
+        }
+        let (field, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(field)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], KeccakPrimeError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u64(&mut self) -> Result<u64, KeccakPrimeError> {
+        Ok(u64::from_be_bytes(self.array()?))
+    }
+
+    fn usize(&mut self) -> Result<usize, KeccakPrimeError> {
+        self.u64()?
+            .try_into()
+            .map_err(|_| KeccakPrimeError::MalformedCheckpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::prime_with_params;
+
+    const PREV_HASH: [u8; INPUT_HASH_SIZE] = [1; INPUT_HASH_SIZE];
+    const ROOT_HASH: [u8; INPUT_HASH_SIZE] = [2; INPUT_HASH_SIZE];
+    const NONCE: [u8; NONCE_SIZE] = [3; NONCE_SIZE];
+
+    fn test_params() -> PrimeParams {
+        PrimeParams::builder()
+            .penalty(10)
+            .delay(5)
+            .vdf_iterations(3)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn stepwise_matches_prime() {
+        let expected = prime_with_params(PREV_HASH, ROOT_HASH, NONCE, &test_params()).unwrap();
+
+        let mut computation =
+            PrimeComputation::new(PREV_HASH, ROOT_HASH, NONCE, test_params()).unwrap();
+        assert_eq!(computation.total_steps(), 15);
+
+        while !computation.is_finished() {
+            assert_eq!(computation.output(), None);
+            assert!(computation.step(4) > 0);
+        }
+        assert_eq!(computation.steps_done(), 15);
+        assert_eq!(computation.step(4), 0);
+        assert_eq!(computation.output(), Some(expected.clone()));
+        assert_eq!(computation.finish(), expected);
+    }
+
+    #[test]
+    fn resume_from_checkpoint() {
+        let mut computation =
+            PrimeComputation::new(PREV_HASH, ROOT_HASH, NONCE, test_params()).unwrap();
+        computation.step(7);
+
+        let checkpoint = computation.to_bytes();
+        let restored = PrimeComputation::from_bytes(&checkpoint).unwrap();
+        assert_eq!(restored, computation);
+        assert_eq!(restored.finish(), computation.finish());
+
+        // The key derivation is a part of the checkpoint.
+        #[cfg(feature = "cshake")]
+        {
+            let params = PrimeParams::builder()
+                .delay(5)
+                .vdf_iterations(1)
+                .kdf(ExpansionKdf::CShake256)
+                .build()
+                .unwrap();
+            let computation = PrimeComputation::new(PREV_HASH, ROOT_HASH, NONCE, params).unwrap();
+            let restored = PrimeComputation::from_bytes(&computation.to_bytes()).unwrap();
+            assert_eq!(restored, computation);
+        }
+    }
+
+    #[test]
+    fn malformed_checkpoints() {
+        let computation =
+            PrimeComputation::new(PREV_HASH, ROOT_HASH, NONCE, test_params()).unwrap();
+        let checkpoint = computation.to_bytes();
+
+        let truncated = &checkpoint[..checkpoint.len() - 1];
+        assert!(PrimeComputation::from_bytes(truncated).is_err());
+
+        let mut trailing = checkpoint.clone();
+        trailing.push(0);
+        assert!(PrimeComputation::from_bytes(&trailing).is_err());
+
+        let mut wrong_magic = checkpoint;
+        wrong_magic[0] ^= 1;
+        assert!(PrimeComputation::from_bytes(&wrong_magic).is_err());
+    }
+}
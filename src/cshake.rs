@@ -2,7 +2,9 @@
 //!
 //! [`SP800-185`]: https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-185.pdf
 
-use crate::{bits_to_rate, keccakf::KeccakF, left_encode, Hasher, KeccakState, Xof};
+use crate::{
+    bits_to_rate, keccakf::KeccakF, left_encode, Hasher, KeccakState, PenalizedHasher, Xof,
+};
 
 /// The `cSHAKE` extendable-output functions defined in [`SP800-185`].
 ///
@@ -55,6 +57,7 @@ impl CShake {
         CShake { state }
     }
 
+    #[cfg(feature = "kmac")]
     pub(crate) fn fill_block(&mut self) {
         self.state.fill_block();
     }
@@ -70,6 +73,12 @@ impl Hasher for CShake {
     }
 }
 
+impl PenalizedHasher for CShake {
+    fn finalize_with_penalty_into(self, penalty: usize, output: &mut [u8]) {
+        self.state.finalize_with_penalty_into(penalty, output);
+    }
+}
+
 impl Xof for CShake {
     fn squeeze(&mut self, output: &mut [u8]) {
         self.state.squeeze(output);
@@ -1,7 +1,10 @@
 //! Implements the expansion function.
 
 use crate::fortuna::*;
+use crate::params::ExpansionKdf;
 use crate::prime::KeccakPrimeError;
+#[cfg(feature = "cshake")]
+use crate::{left_encode, right_encode, CShake, Hasher};
 
 /// Hash inputs sizes, in bytes.
 pub const INPUT_HASH_SIZE: usize = 32; // 256 bits
@@ -9,29 +12,140 @@ pub const INPUT_HASH_SIZE: usize = 32; // 256 bits
 /// Input nonce size, in bytes.
 pub const NONCE_SIZE: usize = 8; // 64 bits
 
+/// Customization string of the [`ExpansionKdf::CShake256`] key derivation.
+#[cfg(feature = "cshake")]
+const KDF_CUSTOMIZATION: &[u8] = b"Keccak-prime expansion key";
+
 /// Takes a previous hash, root merkle hash and nonce as an input.
 /// Outputs a byte sequence of length `output_size` (in bytes) suitable to be used in a VDF permutation function.
+///
+/// The AES key is derived from the inputs with `kdf`.
 pub fn expand(
     prev_hash: [u8; INPUT_HASH_SIZE],
     root_hash: [u8; INPUT_HASH_SIZE],
     nonce: [u8; NONCE_SIZE],
     output_size: usize,
+    kdf: ExpansionKdf,
+) -> Result<Vec<u8>, KeccakPrimeError> {
+    let derived_key = derive_aes_key(kdf, prev_hash, root_hash, nonce);
+    expand_with_key(&derived_key, nonce, output_size)
+}
+
+/// Same as [`expand`], but takes a key previously obtained from a [`KeyDerivation`].
+pub(crate) fn expand_with_key(
+    derived_key: &[u8; 32],
+    nonce: [u8; NONCE_SIZE],
+    output_size: usize,
 ) -> Result<Vec<u8>, KeccakPrimeError> {
-    // Derive an AES key from the previous & Merkle tree hashes.
-    let derived_key = derive_aes_key(prev_hash, root_hash);
     let usage = 256 * (u64::from_be_bytes(nonce) as u128) + 256 - 1;
-    let mut fortuna = Fortuna::new(&derived_key, usage)?;
+    let mut fortuna = Fortuna::new(derived_key, usage)?;
     let result = fortuna.get_bytes(output_size)?;
     Ok(result)
 }
 
 /// Derives a symmetric encryption key for the AES-256 block cipher.
-fn derive_aes_key(prev_hash: [u8; INPUT_HASH_SIZE], root_hash: [u8; INPUT_HASH_SIZE]) -> [u8; 32] {
-    let mut xor_result = [0u8; INPUT_HASH_SIZE];
-    for i in 0..INPUT_HASH_SIZE {
-        xor_result[i] = prev_hash[i] ^ root_hash[i];
+fn derive_aes_key(
+    kdf: ExpansionKdf,
+    prev_hash: [u8; INPUT_HASH_SIZE],
+    root_hash: [u8; INPUT_HASH_SIZE],
+    nonce: [u8; NONCE_SIZE],
+) -> [u8; 32] {
+    let mut derivation = KeyDerivation::new(kdf);
+    derivation.update(&prev_hash);
+    derivation.update(&root_hash);
+    derivation.finalize(nonce)
+}
+
+/// Streaming key derivation over a header of arbitrary length.
+///
+/// The header is split into chunks of [`INPUT_HASH_SIZE`] bytes, the last one possibly shorter,
+/// so that a header made of the previous block hash and the Merkle root hash gives the same
+/// key as [`expand`]:
+/// - [`ExpansionKdf::Xor`] XORs all chunks, padding the last one with zeros.
+/// - [`ExpansionKdf::CShake256`] absorbs each chunk and then the nonce as `encode_string`
+///   from SP800-185, as in TupleHash, so that the boundaries between them are unambiguous.
+#[derive(Clone)]
+pub(crate) struct KeyDerivation {
+    state: KdfState,
+    chunk: [u8; INPUT_HASH_SIZE],
+    chunk_len: usize,
+}
+
+#[derive(Clone)]
+enum KdfState {
+    Xor([u8; 32]),
+    #[cfg(feature = "cshake")]
+    CShake256(CShake),
+}
+
+impl KeyDerivation {
+    pub(crate) fn new(kdf: ExpansionKdf) -> KeyDerivation {
+        let state = match kdf {
+            ExpansionKdf::Xor => KdfState::Xor([0u8; 32]),
+            #[cfg(feature = "cshake")]
+            ExpansionKdf::CShake256 => KdfState::CShake256(CShake::v256(b"", KDF_CUSTOMIZATION)),
+        };
+        KeyDerivation {
+            state,
+            chunk: [0u8; INPUT_HASH_SIZE],
+            chunk_len: 0,
+        }
+    }
+
+    /// Absorbs additional header bytes. Can be called multiple times.
+    pub(crate) fn update(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            let len = input.len().min(INPUT_HASH_SIZE - self.chunk_len);
+            self.chunk[self.chunk_len..][..len].copy_from_slice(&input[..len]);
+            self.chunk_len += len;
+            input = &input[len..];
+
+            if self.chunk_len == INPUT_HASH_SIZE {
+                self.absorb_chunk();
+            }
+        }
+    }
+
+    fn absorb_chunk(&mut self) {
+        let chunk = &self.chunk[..self.chunk_len];
+        match &mut self.state {
+            KdfState::Xor(key) => {
+                for (k, c) in key.iter_mut().zip(chunk) {
+                    *k ^= c;
+                }
+            }
+            #[cfg(feature = "cshake")]
+            KdfState::CShake256(cshake) => {
+                cshake.update(left_encode(chunk.len() * 8).value());
+                cshake.update(chunk);
+            }
+        }
+        self.chunk_len = 0;
+    }
+
+    /// Finishes the header and derives the key for `nonce`.
+    ///
+    /// The XOR key doesn't depend on the nonce, which only sets the Fortuna usage number.
+    #[cfg_attr(not(feature = "cshake"), allow(unused_variables))]
+    pub(crate) fn finalize(mut self, nonce: [u8; NONCE_SIZE]) -> [u8; 32] {
+        if self.chunk_len > 0 {
+            self.absorb_chunk();
+        }
+
+        match self.state {
+            KdfState::Xor(key) => key,
+            #[cfg(feature = "cshake")]
+            KdfState::CShake256(mut cshake) => {
+                cshake.update(left_encode(nonce.len() * 8).value());
+                cshake.update(&nonce);
+
+                let mut key = [0u8; 32];
+                cshake.update(right_encode(key.len() * 8).value());
+                cshake.finalize(&mut key);
+                key
+            }
+        }
     }
-    xor_result
 }
 
 #[cfg(test)]
@@ -47,7 +161,52 @@ mod tests {
 
         let output_size = 136; // 1088 bits
 
-        let res = expand(prev_hash, root_hash, nonce, output_size).expect("expand function failed");
-        assert_eq!(res.len(), output_size);
+        let kdfs = [
+            ExpansionKdf::Xor,
+            #[cfg(feature = "cshake")]
+            ExpansionKdf::CShake256,
+        ];
+        for kdf in kdfs {
+            let res = expand(prev_hash, root_hash, nonce, output_size, kdf)
+                .expect("expand function failed");
+            assert_eq!(res.len(), output_size);
+        }
+    }
+
+    // Swapped, equal or slightly different inputs must give distinct expansion outputs.
+    #[test]
+    #[cfg(feature = "cshake")]
+    fn kdf_separates_inputs() {
+        let (a, b, nonce) = (
+            [1u8; INPUT_HASH_SIZE],
+            [2u8; INPUT_HASH_SIZE],
+            [3u8; NONCE_SIZE],
+        );
+        let expand = |kdf, prev_hash, root_hash, nonce| {
+            expand(prev_hash, root_hash, nonce, 136, kdf).expect("expand function failed")
+        };
+
+        // The XOR key derivation can't tell these apart.
+        let xor = ExpansionKdf::Xor;
+        assert_eq!(expand(xor, a, b, nonce), expand(xor, b, a, nonce));
+        assert_eq!(expand(xor, a, a, nonce), expand(xor, b, b, nonce));
+
+        let kdf = ExpansionKdf::CShake256;
+        let output = expand(kdf, a, b, nonce);
+        assert_ne!(output, expand(kdf, b, a, nonce));
+        assert_ne!(expand(kdf, a, a, nonce), expand(kdf, b, b, nonce));
+        assert_ne!(derive_aes_key(kdf, a, a, nonce), [0u8; 32]);
+
+        for bit in [0, 7, 100, 255] {
+            let mut flipped = a;
+            flipped[bit / 8] ^= 1 << (bit % 8);
+            assert_ne!(output, expand(kdf, flipped, b, nonce));
+            assert_ne!(output, expand(kdf, a, flipped, nonce));
+        }
+        for bit in [0, 63] {
+            let mut flipped = nonce;
+            flipped[bit / 8] ^= 1 << (bit % 8);
+            assert_ne!(output, expand(kdf, a, b, flipped));
+        }
     }
 }
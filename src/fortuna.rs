@@ -3,6 +3,9 @@
 //! the hash is computed externally. Instead, we generate a key before the generation of
 //! pseudorandom data.
 
+// `generic_array` 0.14 is deprecated upstream but is still what `aes-gcm-siv` 0.9 expects.
+#![allow(deprecated)]
+
 use aes_gcm_siv::aead::{generic_array::GenericArray, AeadInPlace, NewAead};
 use aes_gcm_siv::Aes256GcmSiv;
 
@@ -0,0 +1,358 @@
+//! Fixed-width arithmetic modulo the Sloth prime p = 2^1600 - 2273.
+//!
+//! Integers are stored as 25 little-endian 64-bit limbs, which is also the layout of
+//! the Keccak-f[1600] state. All operations work on the stack without allocations, and
+//! the reduction exploits the pseudo-Mersenne form of the modulus: 2^1600 = 2273 (mod p).
+
+use num_bigint::BigUint;
+
+use crate::WORDS;
+
+/// Number of 64-bit limbs.
+pub(crate) const LIMBS: usize = WORDS;
+
+/// Little-endian limbs of a 1600-bit integer.
+pub(crate) type Limbs = [u64; LIMBS];
+
+/// p = 2^1600 - C
+const C: u64 = 2273;
+
+/// The modulus p = 2^1600 - 2273.
+const P: Limbs = {
+    let mut p = [u64::MAX; LIMBS];
+    p[0] = u64::MAX - C + 1;
+    p
+};
+
+/// Turns `0` or `1` into an all-zeroes or an all-ones mask.
+#[inline]
+fn mask(choice: u64) -> u64 {
+    0u64.wrapping_sub(choice)
+}
+
+/// Returns `a` if `choice` is `0` and `b` if `choice` is `1`, without branching.
+#[inline]
+fn select(a: &Limbs, b: &Limbs, choice: u64) -> Limbs {
+    let mask = mask(choice);
+    let mut r = [0u64; LIMBS];
+    for i in 0..LIMBS {
+        r[i] = a[i] ^ (mask & (a[i] ^ b[i]));
+    }
+    r
+}
+
+/// Adds a single word to `a`, returning the result and the carry.
+#[inline]
+fn add_word(a: &Limbs, w: u64) -> (Limbs, u64) {
+    let mut r = [0u64; LIMBS];
+    let mut carry = w;
+    for i in 0..LIMBS {
+        let (sum, overflow) = a[i].overflowing_add(carry);
+        r[i] = sum;
+        carry = overflow as u64;
+    }
+    (r, carry)
+}
+
+/// Subtracts a single word from `a`, returning the result and the borrow.
+#[inline]
+fn sub_word(a: &Limbs, w: u64) -> (Limbs, u64) {
+    let mut r = [0u64; LIMBS];
+    let mut borrow = w;
+    for i in 0..LIMBS {
+        let (diff, overflow) = a[i].overflowing_sub(borrow);
+        r[i] = diff;
+        borrow = overflow as u64;
+    }
+    (r, borrow)
+}
+
+/// Computes `a - b`, returning the result and the borrow.
+#[inline]
+fn sub_limbs(a: &Limbs, b: &Limbs) -> (Limbs, u64) {
+    let mut r = [0u64; LIMBS];
+    let mut borrow = 0;
+    for i in 0..LIMBS {
+        let diff = (a[i] as u128)
+            .wrapping_sub(b[i] as u128)
+            .wrapping_sub(borrow as u128);
+        r[i] = diff as u64;
+        borrow = ((diff >> 64) as u64) & 1;
+    }
+    (r, borrow)
+}
+
+/// Element of the prime field modulo p, always kept in the canonical range `[0, p)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Fp(Limbs);
+
+impl Fp {
+    pub(crate) const ZERO: Fp = Fp([0; LIMBS]);
+
+    pub(crate) const ONE: Fp = {
+        let mut one = [0; LIMBS];
+        one[0] = 1;
+        Fp(one)
+    };
+
+    /// Reduces an arbitrary 1600-bit integer modulo p.
+    pub(crate) fn reduce(x: &Limbs) -> Fp {
+        // x < 2^1600 < 2p, so a single conditional subtraction is enough.
+        let (diff, borrow) = sub_limbs(x, &P);
+        Fp(select(&diff, x, borrow))
+    }
+
+    /// Limbs of the canonical representation.
+    pub(crate) fn limbs(&self) -> &Limbs {
+        &self.0
+    }
+
+    /// Returns `1` if the canonical representation is odd and `0` otherwise.
+    pub(crate) fn is_odd(&self) -> u64 {
+        self.0[0] & 1
+    }
+
+    /// Returns `1` if the elements are equal and `0` otherwise, without branching.
+    pub(crate) fn ct_eq(&self, other: &Fp) -> u64 {
+        let mut diff = 0;
+        for i in 0..LIMBS {
+            diff |= self.0[i] ^ other.0[i];
+        }
+        // The top bit of `diff | -diff` is set if and only if `diff` is non-zero.
+        ((diff | diff.wrapping_neg()) >> 63) ^ 1
+    }
+
+    /// Returns `-self` if `choice` is `1` and `self` if it is `0`, without branching.
+    pub(crate) fn conditional_neg(&self, choice: u64) -> Fp {
+        Fp(select(&self.0, &self.neg().0, choice))
+    }
+
+    pub(crate) fn sub(&self, other: &Fp) -> Fp {
+        let (diff, borrow) = sub_limbs(&self.0, &other.0);
+        // On borrow, `diff` equals `self - other + 2^1600` and we need `self - other + p`.
+        let (diff, _) = sub_word(&diff, C & mask(borrow));
+        Fp(diff)
+    }
+
+    pub(crate) fn neg(&self) -> Fp {
+        Fp::ZERO.sub(self)
+    }
+
+    pub(crate) fn mul(&self, other: &Fp) -> Fp {
+        let (a, b) = (&self.0, &other.0);
+
+        // Schoolbook multiplication into a 3200-bit product.
+        let mut product = [0u64; 2 * LIMBS];
+        for i in 0..LIMBS {
+            let mut carry = 0u64;
+            for j in 0..LIMBS {
+                let t = product[i + j] as u128 + a[i] as u128 * b[j] as u128 + carry as u128;
+                product[i + j] = t as u64;
+                carry = (t >> 64) as u64;
+            }
+            product[i + LIMBS] = carry;
+        }
+
+        Fp::reduce_wide(&product)
+    }
+
+    pub(crate) fn square(&self) -> Fp {
+        let a = &self.0;
+
+        // Cross products a[i] * a[j] for i < j, each computed once.
+        let mut product = [0u64; 2 * LIMBS];
+        for i in 0..LIMBS {
+            let mut carry = 0u64;
+            for j in (i + 1)..LIMBS {
+                let t = product[i + j] as u128 + a[i] as u128 * a[j] as u128 + carry as u128;
+                product[i + j] = t as u64;
+                carry = (t >> 64) as u64;
+            }
+            product[i + LIMBS] = carry;
+        }
+
+        // Double them, the sum is below 2^3199 so the top bit is free.
+        for i in (1..2 * LIMBS).rev() {
+            product[i] = (product[i] << 1) | (product[i - 1] >> 63);
+        }
+        product[0] <<= 1;
+
+        // Add the squares a[i]^2 on the diagonal.
+        let mut carry = 0u128;
+        for i in 0..LIMBS {
+            let square = a[i] as u128 * a[i] as u128;
+            let t = product[2 * i] as u128 + (square as u64) as u128 + carry;
+            product[2 * i] = t as u64;
+            let t = product[2 * i + 1] as u128 + (square >> 64) + (t >> 64);
+            product[2 * i + 1] = t as u64;
+            carry = t >> 64;
+        }
+
+        Fp::reduce_wide(&product)
+    }
+
+    /// Squares `n` times.
+    fn square_n(&self, n: usize) -> Fp {
+        let mut x = *self;
+        for _ in 0..n {
+            x = x.square();
+        }
+        x
+    }
+
+    /// Raises to the power (p - 3) / 4 = 2^1598 - 569.
+    ///
+    /// Almost all bits of the exponent are set, so instead of square-and-multiply a fixed
+    /// addition chain is used: 1602 squarings and 16 multiplications.
+    pub(crate) fn pow_sqrt_exponent(&self) -> Fp {
+        // x_k = x^(2^k - 1), x_(a+b) = x_a^(2^b) * x_b
+        let x1 = *self;
+        let x2 = x1.square().mul(&x1);
+        let x3 = x2.square().mul(&x1);
+        let x4 = x3.square().mul(&x1);
+        let x6 = x3.square_n(3).mul(&x3);
+        let x12 = x6.square_n(6).mul(&x6);
+        let x24 = x12.square_n(12).mul(&x12);
+        let x48 = x24.square_n(24).mul(&x24);
+        let x52 = x48.square_n(4).mul(&x4);
+        let x96 = x48.square_n(48).mul(&x48);
+        let x192 = x96.square_n(96).mul(&x96);
+        let x384 = x192.square_n(192).mul(&x192);
+        let x768 = x384.square_n(384).mul(&x384);
+        let x1536 = x768.square_n(768).mul(&x768);
+        let x1588 = x1536.square_n(52).mul(&x52);
+
+        // 2^1598 - 569 = (2^1588 - 1) * 2^10 + 0b0111000111
+        x1588.square_n(4).mul(&x3).square_n(6).mul(&x3)
+    }
+
+    /// Reduces a 3200-bit product `lo + hi * 2^1600` as `lo + hi * C`.
+    fn reduce_wide(product: &[u64; 2 * LIMBS]) -> Fp {
+        let mut r = [0u64; LIMBS];
+        let mut carry = 0u128;
+        for i in 0..LIMBS {
+            let t = product[i] as u128 + product[i + LIMBS] as u128 * C as u128 + carry;
+            r[i] = t as u64;
+            carry = t >> 64;
+        }
+
+        // The remaining carry is below C, fold it in once more.
+        let (r, overflow) = add_word(&r, carry as u64 * C);
+        // On overflow the value is tiny, so adding C can't overflow again.
+        let (r, _) = add_word(&r, C & mask(overflow));
+        Fp::reduce(&r)
+    }
+}
+
+/// Returns `true` if `x` is below p.
+pub(crate) fn is_reduced(x: &Limbs) -> bool {
+    sub_limbs(x, &P).1 == 1
+}
+
+/// Converts limbs into a big integer.
+pub(crate) fn limbs_to_biguint(limbs: &Limbs) -> BigUint {
+    let mut bytes = [0u8; LIMBS * 8];
+    for (chunk, limb) in bytes.chunks_exact_mut(8).zip(limbs) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    BigUint::from_bytes_le(&bytes)
+}
+
+/// Converts a big integer into limbs. Returns `None` if it doesn't fit into 1600 bits.
+pub(crate) fn biguint_to_limbs(x: &BigUint) -> Option<Limbs> {
+    let digits = x.to_u64_digits();
+    if digits.len() > LIMBS {
+        return None;
+    }
+    let mut limbs = [0u64; LIMBS];
+    limbs[..digits.len()].copy_from_slice(&digits);
+    Some(limbs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::One;
+
+    fn modulus() -> BigUint {
+        (BigUint::one() << 1600) - BigUint::from(C)
+    }
+
+    /// Deterministic pseudorandom limbs.
+    fn test_limbs(seed: u64) -> Limbs {
+        let mut state = seed;
+        let mut limbs = [0u64; LIMBS];
+        for limb in limbs.iter_mut() {
+            // splitmix64
+            state = state.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            *limb = z ^ (z >> 31);
+        }
+        limbs
+    }
+
+    fn test_values() -> Vec<Limbs> {
+        let p = biguint_to_limbs(&modulus()).unwrap();
+        let mut values = vec![
+            [0; LIMBS],
+            Fp::ONE.0,
+            sub_word(&p, 1).0,
+            p,
+            [u64::MAX; LIMBS],
+        ];
+        values.extend((0..16).map(test_limbs));
+        values
+    }
+
+    #[test]
+    fn constants() {
+        let p = modulus();
+        assert_eq!(limbs_to_biguint(&P), p);
+    }
+
+    // Compare the fixed-width arithmetic with `BigUint`.
+    #[test]
+    fn differential_arithmetic() {
+        let p = modulus();
+        for a in test_values() {
+            for b in test_values() {
+                let (fa, fb) = (Fp::reduce(&a), Fp::reduce(&b));
+                let (ba, bb) = (limbs_to_biguint(&a) % &p, limbs_to_biguint(&b) % &p);
+
+                assert_eq!(limbs_to_biguint(&fa.0), ba);
+                assert_eq!(limbs_to_biguint(&fa.sub(&fb).0), (&ba + &p - &bb) % &p);
+                assert_eq!(limbs_to_biguint(&fa.mul(&fb).0), (&ba * &bb) % &p);
+            }
+            assert_eq!(Fp::reduce(&a).square(), Fp::reduce(&a).mul(&Fp::reduce(&a)));
+        }
+    }
+
+    #[test]
+    fn differential_pow() {
+        let p = modulus();
+        let exponent = (&p - BigUint::from(3u8)) / BigUint::from(4u8);
+        for a in test_values().into_iter().take(8) {
+            let fa = Fp::reduce(&a);
+            let ba = limbs_to_biguint(&a);
+            assert_eq!(
+                limbs_to_biguint(&fa.pow_sqrt_exponent().0),
+                ba.modpow(&exponent, &p)
+            );
+            assert_eq!(limbs_to_biguint(&fa.neg().0), (&p - &ba % &p) % &p);
+            assert_eq!(fa.conditional_neg(1), fa.neg());
+            assert_eq!(fa.conditional_neg(0), fa);
+        }
+    }
+
+    #[test]
+    fn ct_eq() {
+        for a in test_values() {
+            for b in test_values() {
+                let (fa, fb) = (Fp::reduce(&a), Fp::reduce(&b));
+                assert_eq!(fa.ct_eq(&fb), (fa == fb) as u64);
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Block header with a canonical byte encoding and the Keccak-prime proof of work.
+
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+
+use crate::{
+    expansion::{INPUT_HASH_SIZE, NONCE_SIZE},
+    mining::Target,
+    params::PrimeParams,
+    prime::{KeccakPrime, KeccakPrimeError},
+    Hasher,
+};
+
+/// Block header committed to by the Keccak-prime proof of work.
+///
+/// The canonical encoding is the concatenation of all fields in the declaration order, with
+/// integers in big-endian byte order, [`BlockHeader::ENCODED_SIZE`] bytes in total:
+///
+/// | field         | size |
+/// |---------------|------|
+/// | `version`     | 4    |
+/// | `prev_hash`   | 32   |
+/// | `merkle_root` | 32   |
+/// | `timestamp`   | 8    |
+/// | `difficulty`  | 4    |
+/// | `nonce`       | 8    |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockHeader {
+    /// Header version.
+    pub version: u32,
+    /// Hash of the previous block.
+    pub prev_hash: [u8; INPUT_HASH_SIZE],
+    /// Merkle root hash of the block transactions.
+    pub merkle_root: [u8; INPUT_HASH_SIZE],
+    /// Block time, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Required number of leading zero bits in the proof of work hash.
+    pub difficulty: u32,
+    /// Proof of work nonce.
+    pub nonce: u64,
+}
+
+impl BlockHeader {
+    /// Size of the canonical encoding, in bytes.
+    pub const ENCODED_SIZE: usize = 4 + 2 * INPUT_HASH_SIZE + 8 + 4 + NONCE_SIZE;
+
+    /// Encodes the header into its canonical byte representation.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_SIZE] {
+        let mut bytes = [0u8; Self::ENCODED_SIZE];
+        bytes[..Self::ENCODED_SIZE - NONCE_SIZE].copy_from_slice(&self.pow_header());
+        bytes[Self::ENCODED_SIZE - NONCE_SIZE..].copy_from_slice(&self.nonce.to_be_bytes());
+        bytes
+    }
+
+    /// Decodes a header from its canonical byte representation.
+    pub fn from_bytes(bytes: &[u8]) -> Result<BlockHeader, HeaderError> {
+        if bytes.len() != Self::ENCODED_SIZE {
+            return Err(HeaderError::InvalidLength(bytes.len()));
+        }
+
+        let (version, bytes) = bytes.split_at(4);
+        let (prev_hash, bytes) = bytes.split_at(INPUT_HASH_SIZE);
+        let (merkle_root, bytes) = bytes.split_at(INPUT_HASH_SIZE);
+        let (timestamp, bytes) = bytes.split_at(8);
+        let (difficulty, nonce) = bytes.split_at(4);
+
+        Ok(BlockHeader {
+            version: u32::from_be_bytes(version.try_into().unwrap()),
+            prev_hash: prev_hash.try_into().unwrap(),
+            merkle_root: merkle_root.try_into().unwrap(),
+            timestamp: u64::from_be_bytes(timestamp.try_into().unwrap()),
+            difficulty: u32::from_be_bytes(difficulty.try_into().unwrap()),
+            nonce: u64::from_be_bytes(nonce.try_into().unwrap()),
+        })
+    }
+
+    /// Keccak-prime hash of the header, `params.output_len()` bytes long.
+    ///
+    /// The canonical encoding without the nonce is absorbed by [`KeccakPrime`], and the nonce
+    /// is supplied to it in big-endian byte order. The header doesn't consist of two 32-byte
+    /// hashes, so `params` must use the
+    /// [`ExpansionKdf::CShake256`](crate::params::ExpansionKdf::CShake256) key derivation,
+    /// e.g. [`PrimeVersion::V2`](crate::version::PrimeVersion), otherwise
+    /// [`KeccakPrimeError::UnsupportedKdf`] is returned.
+    pub fn pow_hash(&self, params: &PrimeParams) -> Result<Vec<u8>, KeccakPrimeError> {
+        let mut keccak_prime = KeccakPrime::new(params.clone(), self.nonce.to_be_bytes())?;
+        keccak_prime.update(&self.pow_header());
+
+        let mut output = vec![0u8; params.output_len()];
+        keccak_prime.finalize(&mut output);
+        Ok(output)
+    }
+
+    /// Difficulty target of the header.
+    pub fn target(&self) -> Target {
+        Target::LeadingZeros(self.difficulty)
+    }
+
+    /// Checks whether the proof of work hash of the header meets its [`target`].
+    /// Fails on the same parameters as [`pow_hash`].
+    ///
+    /// [`target`]: BlockHeader::target
+    /// [`pow_hash`]: BlockHeader::pow_hash
+    pub fn meets_target(&self, params: &PrimeParams) -> Result<bool, KeccakPrimeError> {
+        Ok(self.target().is_met_by(&self.pow_hash(params)?))
+    }
+
+    /// Canonical encoding without the nonce.
+    fn pow_header(&self) -> [u8; Self::ENCODED_SIZE - NONCE_SIZE] {
+        let mut bytes = [0u8; Self::ENCODED_SIZE - NONCE_SIZE];
+        let fields: [&[u8]; 5] = [
+            &self.version.to_be_bytes(),
+            &self.prev_hash,
+            &self.merkle_root,
+            &self.timestamp.to_be_bytes(),
+            &self.difficulty.to_be_bytes(),
+        ];
+
+        let mut offset = 0;
+        for field in fields {
+            bytes[offset..][..field.len()].copy_from_slice(field);
+            offset += field.len();
+        }
+        bytes
+    }
+}
+
+/// Invalid encoding of a [`BlockHeader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderError {
+    /// The encoding isn't [`BlockHeader::ENCODED_SIZE`] bytes long.
+    InvalidLength(usize),
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderError::InvalidLength(len) => write!(
+                f,
+                "block header of {} bytes must be {} bytes long",
+                len,
+                BlockHeader::ENCODED_SIZE
+            ),
+        }
+    }
+}
+
+impl Error for HeaderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{params::ExpansionKdf, version::PrimeVersion};
+
+    fn test_header() -> BlockHeader {
+        BlockHeader {
+            version: 1,
+            prev_hash: [0xaa; INPUT_HASH_SIZE],
+            merkle_root: [0xbb; INPUT_HASH_SIZE],
+            timestamp: 0x0102030405060708,
+            difficulty: 0,
+            nonce: 0x1112131415161718,
+        }
+    }
+
+    #[test]
+    fn canonical_encoding() {
+        let header = test_header();
+        let bytes = header.to_bytes();
+
+        assert_eq!(bytes.len(), 88);
+        assert_eq!(bytes[..4], [0, 0, 0, 1]);
+        assert_eq!(bytes[4..36], [0xaa; 32]);
+        assert_eq!(bytes[36..68], [0xbb; 32]);
+        assert_eq!(bytes[68..76], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(bytes[76..80], [0, 0, 0, 0]);
+        assert_eq!(
+            bytes[80..],
+            [0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18]
+        );
+
+        assert_eq!(BlockHeader::from_bytes(&bytes), Ok(header));
+        assert_eq!(
+            BlockHeader::from_bytes(&bytes[..87]),
+            Err(HeaderError::InvalidLength(87))
+        );
+        assert_eq!(
+            BlockHeader::from_bytes(&[bytes.as_ref(), &[0]].concat()),
+            Err(HeaderError::InvalidLength(89))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "cshake")]
+    fn proof_of_work() {
+        let params = PrimeParams::builder()
+            .penalty(10)
+            .delay(10)
+            .vdf_iterations(1)
+            .kdf(ExpansionKdf::CShake256)
+            .build()
+            .unwrap();
+        let header = test_header();
+        let hash = header.pow_hash(&params).unwrap();
+        assert_eq!(hash.len(), 32);
+
+        // Every field is committed to.
+        let changes: [fn(&mut BlockHeader); 7] = [
+            |h| h.version += 1,
+            |h| h.prev_hash[31] ^= 1,
+            |h| h.merkle_root[0] ^= 1,
+            // Equal flips at the same offset of both hashes, which XOR would cancel out.
+            |h| {
+                h.prev_hash[0] ^= 1;
+                h.merkle_root[0] ^= 1;
+            },
+            |h| h.timestamp += 1,
+            |h| h.difficulty += 1,
+            |h| h.nonce += 1,
+        ];
+        for change in changes {
+            let mut changed = header;
+            change(&mut changed);
+            assert_ne!(changed.pow_hash(&params).unwrap(), hash);
+        }
+
+        assert!(header.meets_target(&params).unwrap());
+        let impossible = BlockHeader {
+            difficulty: 257,
+            ..header
+        };
+        assert!(!impossible.meets_target(&params).unwrap());
+    }
+
+    // The versions with the XOR key derivation can't be used for headers.
+    #[test]
+    fn unsupported_params() {
+        let header = test_header();
+        for version in [PrimeVersion::V0, PrimeVersion::V1] {
+            let params = version.params(10, 10, 1).unwrap();
+            assert!(matches!(
+                header.pow_hash(&params),
+                Err(KeccakPrimeError::UnsupportedKdf(ExpansionKdf::Xor))
+            ));
+            assert!(matches!(
+                header.meets_target(&params),
+                Err(KeccakPrimeError::UnsupportedKdf(ExpansionKdf::Xor))
+            ));
+        }
+
+        #[cfg(feature = "cshake")]
+        assert!(header
+            .pow_hash(&PrimeVersion::V2.params(10, 10, 1).unwrap())
+            .is_ok());
+    }
+}
@@ -1,5 +1,9 @@
 //! Inverse Keccak functions.
 
+use crate::WORDS;
+
+/// Coefficients of the inverse theta: bit `z` of the entry `x_off` is set if the lane parity
+/// of column `x - x_off`, rotated by `z`, contributes to column `x`.
 pub(crate) const INVERSE_THETA_POS64: [u64; 5] = [
     0xDE26BC4D789AF134,
     0x09AF135E26BC4D78,
@@ -62,64 +66,51 @@ macro_rules! inverse_keccak_function {
 
 
                 // Inverse theta
-                unroll! {
-                    for x in 0..5 {
-                        array[x] = a[idx(x, 0)];
-                        unroll! {
-                            for y in 1..5 {
-                                array[x] ^= a[idx(x, y)];
-                            }
-                        }
-                    }
-                }
+                $crate::inverse::inverse_theta(a);
+            }
+        }
+    }
+}
 
-                const LANE_SIZE: usize = 64;
-                let mut inverse_positions: [u64; 5] = [0; 5];
+pub(crate) use inverse_keccak_function;
 
-                unroll! {
-                    for x in 0..5 {
-                        inverse_positions[x] ^= $crate::inverse::INVERSE_THETA_POS64[x];
-                    }
-                }
+/// Inverts the theta step.
+///
+/// Theta adds `D[x] = C[x - 1] ^ rot(C[x + 1], 1)` to every lane of the column `x`, where `C` are
+/// the column parities. Its inverse adds `E[x] = sum(rot(C'[x - x_off], z))` over the bits `z`
+/// set in `INVERSE_THETA_POS64[x_off]`, where `C'` are the column parities of the output. Each of
+/// these sums is a carry-less product of a lane with a constant, computed with whole-lane
+/// rotations.
+#[inline]
+pub(crate) fn inverse_theta(a: &mut [u64; WORDS]) {
+    let mut parities = [0u64; 5];
+    for (x, parity) in parities.iter_mut().enumerate() {
+        *parity = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+    }
 
-                for _z in 0..LANE_SIZE {
-                    for x_off in 0..5 {
-                        unroll! {
-                            for x in 0..5 {
-                                unroll! {
-                                    for y in 0..5 {
-                                        if (inverse_positions[x_off] & 1) != 0 {
-                                            // TODO: optimise array indexing.
-                                            let mut array_idx: isize = x as isize - x_off as isize;
-                                            array_idx %= 5;
-
-                                            if array_idx < 0 {
-                                                array_idx += 5;
-                                            }
-
-                                            a[idx(x, y)] ^= array[array_idx as usize];
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    for x_off in 0..5 {
-                        array[x_off] = array[x_off].rotate_left(1);
-                        inverse_positions[x_off] >>= 1;
-                    }
-                }
+    let mut effect = [0u64; 5];
+    for (x_off, positions) in INVERSE_THETA_POS64.iter().enumerate() {
+        for (x, parity) in parities.iter().enumerate() {
+            let mut bits = *positions;
+            let mut sum = 0;
+            while bits != 0 {
+                sum ^= parity.rotate_left(bits.trailing_zeros());
+                bits &= bits - 1;
             }
+            effect[(x + x_off) % 5] ^= sum;
         }
     }
-}
 
-pub(crate) use inverse_keccak_function;
+    for (i, lane) in a.iter_mut().enumerate() {
+        *lane ^= effect[i % 5];
+    }
+}
 
 #[cfg(test)]
 mod tests {
+    use super::{inverse_theta, INVERSE_THETA_POS64};
     use crate::keccakf::RC;
-    use crate::Buffer;
+    use crate::{Buffer, WORDS};
 
     // Define a Keccak-F and its inverse.
     keccak_function!("`keccak-f[1600, 8]`", keccakf_8, 8, RC);
@@ -141,4 +132,69 @@ mod tests {
         // Verify that we've got all zeroes back as a result.
         assert!(test_buf.words().iter().all(|word| *word == 0));
     }
+
+    /// The original bit-serial inverse theta, used as a reference.
+    fn bit_serial_inverse_theta(a: &mut [u64; WORDS]) {
+        let mut array = [0u64; 5];
+        for x in 0..5 {
+            for y in 0..5 {
+                array[x] ^= a[x + 5 * y];
+            }
+        }
+
+        let mut inverse_positions = INVERSE_THETA_POS64;
+        for _z in 0..64 {
+            for x_off in 0..5 {
+                if (inverse_positions[x_off] & 1) != 0 {
+                    for x in 0..5 {
+                        for y in 0..5 {
+                            a[x + 5 * y] ^= array[(x + 5 - x_off) % 5];
+                        }
+                    }
+                }
+            }
+            for x_off in 0..5 {
+                array[x_off] = array[x_off].rotate_left(1);
+                inverse_positions[x_off] >>= 1;
+            }
+        }
+    }
+
+    fn theta(a: &mut [u64; WORDS]) {
+        let mut parities = [0u64; 5];
+        for x in 0..5 {
+            parities[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for (i, lane) in a.iter_mut().enumerate() {
+            let x = i % 5;
+            *lane ^= parities[(x + 4) % 5] ^ parities[(x + 1) % 5].rotate_left(1);
+        }
+    }
+
+    #[test]
+    fn fast_inverse_theta() {
+        // splitmix64
+        let mut seed = 0u64;
+        let mut next = || {
+            seed = seed.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+
+        for _ in 0..64 {
+            let mut state = [0u64; WORDS];
+            state.iter_mut().for_each(|lane| *lane = next());
+
+            let mut expected = state;
+            bit_serial_inverse_theta(&mut expected);
+            let mut actual = state;
+            inverse_theta(&mut actual);
+            assert_eq!(actual, expected);
+
+            theta(&mut actual);
+            assert_eq!(actual, state);
+        }
+    }
 }
@@ -60,14 +60,14 @@ impl<T: AsRef<[u8]>> Hasher for KangarooTwelve<T> {
             self.written += todo;
             to_absorb = &to_absorb[todo..];
 
-            if to_absorb.len() > 0 && self.written == Self::MAX_CHUNK_SIZE {
+            if !to_absorb.is_empty() && self.written == Self::MAX_CHUNK_SIZE {
                 self.state.update(&[0x03, 0, 0, 0, 0, 0, 0, 0]);
                 self.written = 0;
                 self.chunks += 1;
             }
         }
 
-        while to_absorb.len() > 0 {
+        while !to_absorb.is_empty() {
             if self.written == Self::MAX_CHUNK_SIZE {
                 let mut chunk_hash = [0u8; 32];
                 let current_chunk = self.current_chunk.clone();
@@ -103,7 +103,7 @@ impl<T: AsRef<[u8]>> Hasher for KangarooTwelve<T> {
 /// # Example
 ///
 /// ```
-/// # use tiny_keccak::{KangarooTwelve, Xof, IntoXof, Hasher};
+/// # use keccak_prime::{KangarooTwelve, Xof, IntoXof, Hasher};
 /// let input = b"hello world";
 /// let mut output = [0u8; 64];
 /// let mut hasher = KangarooTwelve::new(b"");
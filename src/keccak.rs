@@ -1,6 +1,8 @@
 //! The `Keccak` hash functions.
 
-use super::{bits_to_rate, keccakf::KeccakF, Hasher, KeccakState};
+use super::{
+    bits_to_rate, keccakf::KeccakF, Hasher, KeccakState, PenalizedHasher, Permutation, Xof, WORDS,
+};
 
 /// The `Keccak` hash functions defined in [`Keccak SHA3 submission`].
 ///
@@ -11,15 +13,18 @@ use super::{bits_to_rate, keccakf::KeccakF, Hasher, KeccakState};
 /// tiny-keccak = { version = "2.0.0", features = ["keccak"] }
 /// ```
 ///
+/// The `keccak-f[1600, 24]` implementation is selected with the permutation type `P`,
+/// see [`Keccak::with_permutation`].
+///
 /// [`Keccak SHA3 submission`]: https://keccak.team/files/Keccak-submission-3.pdf
 #[derive(Clone)]
-pub struct Keccak {
-    state: KeccakState<KeccakF>,
+pub struct Keccak<P = KeccakF> {
+    state: KeccakState<P>,
 }
 
-impl Keccak {
-    const DELIM: u8 = 0x01;
+const DELIM: u8 = 0x01;
 
+impl Keccak {
     /// Creates  new [`Keccak`] hasher with a security level of 224 bits.
     ///
     /// [`Keccak`]: struct.Keccak.html
@@ -51,20 +56,64 @@ impl Keccak {
     /// Creates new [`Keccak`] hasher with a specified rate.
     pub fn new(bits: usize) -> Keccak {
         // dbg!(bits, bits_to_rate(bits));
+        Keccak::with_permutation(bits)
+    }
+
+    /// Creates new [`Keccak`] hasher with an explicit rate in bytes and capacity in bits.
+    ///
+    /// Unlike [`Keccak::new`], which takes a security level and derives the rate from it,
+    /// this constructor uses the sponge parameters as they are.
+    /// `Keccak::with_rate_capacity(136, 512)` is equal to [`Keccak::v256`].
+    ///
+    /// # Panics
+    ///
+    /// If the rate is zero, or if the rate and the capacity don't add up to the 1600-bit state.
+    pub fn with_rate_capacity(rate: usize, capacity: usize) -> Keccak {
+        assert_eq!(
+            rate * 8 + capacity,
+            WORDS * 64,
+            "rate and capacity must add up to the state size"
+        );
         Keccak {
-            state: KeccakState::new(bits_to_rate(bits), Self::DELIM),
+            state: KeccakState::new(rate, DELIM),
         }
     }
 }
 
-impl Keccak {
+impl<P: Permutation> Keccak<P> {
+    /// Creates new [`Keccak`] hasher with a specified rate over the permutation `P`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use keccak_prime::{Hasher, Keccak, KeccakF};
+    /// let mut keccak = Keccak::<KeccakF>::with_permutation(256);
+    /// let mut output = [0u8; 32];
+    /// keccak.update(b"hello world");
+    /// keccak.finalize(&mut output);
+    /// ```
+    pub fn with_permutation(bits: usize) -> Keccak<P> {
+        Keccak {
+            state: KeccakState::new(bits_to_rate(bits), DELIM),
+        }
+    }
+
     /// Squeeze the state to the output (256 bits) and apply an extra number of permutations.
     pub fn finalize_with_penalty(self, penalty: usize) -> [u8; 32] {
         self.state.finalize_with_penalty(penalty)
     }
+
+    /// Apply an extra number of permutations and convert the hasher into a [`KeccakXof`],
+    /// which can squeeze an output of arbitrary length.
+    ///
+    /// [`KeccakXof`]: struct.KeccakXof.html
+    pub fn into_xof_with_penalty(mut self, penalty: usize) -> KeccakXof<P> {
+        self.state.penalize(penalty);
+        KeccakXof { state: self.state }
+    }
 }
 
-impl Hasher for Keccak {
+impl<P: Permutation> Hasher for Keccak<P> {
     /// Absorb additional input. Can be called multiple times.
     ///
     /// # Example
@@ -101,9 +150,32 @@ impl Hasher for Keccak {
     }
 }
 
+impl<P: Permutation> PenalizedHasher for Keccak<P> {
+    fn finalize_with_penalty_into(self, penalty: usize, output: &mut [u8]) {
+        self.state.finalize_with_penalty_into(penalty, output)
+    }
+}
+
+/// Extendable-output counterpart of the penalized [`Keccak`].
+///
+/// Created by [`Keccak::into_xof_with_penalty`].
+///
+/// [`Keccak`]: struct.Keccak.html
+/// [`Keccak::into_xof_with_penalty`]: struct.Keccak.html#method.into_xof_with_penalty
+#[derive(Clone)]
+pub struct KeccakXof<P = KeccakF> {
+    state: KeccakState<P>,
+}
+
+impl<P: Permutation> Xof for KeccakXof<P> {
+    fn squeeze(&mut self, output: &mut [u8]) {
+        self.state.squeeze(output)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Hasher, Keccak};
+    use super::{Hasher, Keccak, PenalizedHasher, Xof};
 
     // Keccak without any extra penalty must be equal to the original Keccak.
     #[test]
@@ -127,4 +199,58 @@ mod tests {
 
         assert_ne!(output_orig, output_penalized);
     }
+
+    // The fixed-size penalized output must be a prefix of the arbitrary length one.
+    #[test]
+    fn penalized_output_lengths() {
+        let mut keccak = Keccak::v256();
+        keccak.update(&[1, 2, 3]);
+        let output_fixed = keccak.clone().finalize_with_penalty(10);
+
+        let mut output_long = [0; 200];
+        keccak.finalize_with_penalty_into(10, &mut output_long);
+
+        assert_eq!(output_fixed, output_long[..32]);
+    }
+
+    // Squeezing the penalized XOF must continue the penalized output.
+    #[test]
+    fn penalized_xof() {
+        let mut keccak = Keccak::v256();
+        keccak.update(&[1, 2, 3]);
+
+        let mut output = [0; 300];
+        keccak.clone().finalize_with_penalty_into(10, &mut output);
+
+        let mut xof = keccak.into_xof_with_penalty(10);
+        let mut output_xof = [0; 300];
+        xof.squeeze(&mut output_xof[..64]);
+        xof.squeeze(&mut output_xof[64..]);
+
+        assert_eq!(output[..], output_xof[..]);
+    }
+
+    #[test]
+    fn explicit_rate_capacity() {
+        let hash = |mut keccak: Keccak| {
+            keccak.update(&[1, 2, 3]);
+            keccak.finalize_with_penalty(0)
+        };
+
+        assert_eq!(
+            hash(Keccak::with_rate_capacity(136, 512)),
+            hash(Keccak::v256())
+        );
+        // `Keccak::new(1088 / 8)` is a security level of 136 bits, not a rate of 136 bytes.
+        assert_eq!(
+            hash(Keccak::with_rate_capacity(166, 272)),
+            hash(Keccak::new(1088 / 8))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "rate and capacity must add up to the state size")]
+    fn mismatched_rate_capacity() {
+        Keccak::with_rate_capacity(136, 272);
+    }
 }
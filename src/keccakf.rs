@@ -1,4 +1,5 @@
-use crate::{Buffer, Permutation};
+use crate::inverse::inverse_keccak_function;
+use crate::{Permutation, WORDS};
 
 const ROUNDS: usize = 24;
 
@@ -30,12 +31,109 @@ pub(crate) const RC: [u64; ROUNDS] = [
 ];
 
 keccak_function!("`keccak-f[1600, 24]`", keccakf, ROUNDS, RC);
+inverse_keccak_function!("inverse `keccak-f[1600, 24]`", inverse_keccakf, ROUNDS, RC);
 
-#[derive(Clone)]
+/// Implementation of `keccak-f[1600, 24]`, selectable at runtime.
+///
+/// All backends compute the same permutation, so the choice only affects performance.
+/// The sponge constructions select the backend through their [`Permutation`] type
+/// instead, e.g. `Keccak<KeccakFLaneComplementing>`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeccakFBackend {
+    /// Unrolled reference implementation, [`keccakf`].
+    Reference,
+    /// Lane complementing implementation with fused round steps,
+    /// [`keccakf_lane_complementing`](crate::keccakf_lane_complementing).
+    #[cfg(feature = "lane_complementing")]
+    LaneComplementing,
+}
+
+impl KeccakFBackend {
+    /// Applies `keccak-f[1600, 24]` to the state with this backend.
+    pub fn permute(self, state: &mut [u64; WORDS]) {
+        match self {
+            KeccakFBackend::Reference => keccakf(state),
+            #[cfg(feature = "lane_complementing")]
+            KeccakFBackend::LaneComplementing => {
+                crate::keccakf_lc::keccakf_lane_complementing(state)
+            }
+        }
+    }
+}
+
+/// `keccak-f[1600, 24]` with the [`KeccakFBackend::Reference`] backend, used by default
+/// in the sponge constructions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct KeccakF;
 
 impl Permutation for KeccakF {
-    fn execute(buffer: &mut Buffer) {
-        keccakf(buffer.words());
+    fn permute(state: &mut [u64; WORDS]) {
+        keccakf(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keccak-f[1600] applied to the all-zero state, from the Keccak team's known-answer tests.
+    const ZERO_STATE_KAT: [u64; WORDS] = [
+        0xf1258f7940e1dde7,
+        0x84d5ccf933c0478a,
+        0xd598261ea65aa9ee,
+        0xbd1547306f80494d,
+        0x8b284e056253d057,
+        0xff97a42d7f8e6fd4,
+        0x90fee5a0a44647c4,
+        0x8c5bda0cd6192e76,
+        0xad30a6f71b19059c,
+        0x30935ab7d08ffc64,
+        0xeb5aa93f2317d635,
+        0xa9a6e6260d712103,
+        0x81a57c16dbcf555f,
+        0x43b831cd0347c826,
+        0x01f22f1a11a5569f,
+        0x05e5635a21d9ae61,
+        0x64befef28cc970f2,
+        0x613670957bc46611,
+        0xb87c5a554fd00ecb,
+        0x8c3ee88a1ccf32c8,
+        0x940c7922ae3a2614,
+        0x1841f924a2c509e4,
+        0x16f53526e70465c2,
+        0x75f644e97f30a13b,
+        0xeaf1ff7b5ceca249,
+    ];
+
+    #[test]
+    fn known_answer() {
+        let mut state = [0u64; WORDS];
+        keccakf(&mut state);
+        assert_eq!(state, ZERO_STATE_KAT);
+
+        inverse_keccakf(&mut state);
+        assert_eq!(state, [0u64; WORDS]);
+    }
+
+    #[test]
+    fn inverse_random_states() {
+        let mut seed = 0x0123456789abcdefu64;
+        for _ in 0..16 {
+            let mut state = [0u64; WORDS];
+            for lane in state.iter_mut() {
+                // xorshift64
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                *lane = seed;
+            }
+
+            let mut permuted = state;
+            keccakf(&mut permuted);
+            assert_ne!(permuted, state);
+            inverse_keccakf(&mut permuted);
+            assert_eq!(permuted, state);
+        }
     }
 }
@@ -0,0 +1,185 @@
+//! Keccak-f[1600] with the lane complementing transform and fused round steps.
+//!
+//! Six lanes of the state are kept complemented between rounds, which saves most of the
+//! NOT operations in chi. Theta, rho, pi, chi and iota are merged into a single pass over
+//! the state, with the lane indices and rotation offsets resolved at compile time.
+//!
+//! See "Keccak implementation overview" by G. Bertoni, J. Daemen, M. Peeters,
+//! G. Van Assche and R. Van Keer, section 2.2.
+
+use crate::{keccakf::RC, Permutation, WORDS};
+
+/// Lanes which are kept complemented: (1, 0), (2, 0), (3, 1), (2, 2), (2, 3) and (0, 4).
+const COMPLEMENTED: [usize; 6] = [1, 2, 8, 12, 17, 20];
+
+/// `keccak-f[1600, 24]` using the lane complementing transform.
+///
+/// The result is identical to [`keccakf`](crate::keccakf).
+pub fn keccakf_lane_complementing(state: &mut [u64; WORDS]) {
+    for i in COMPLEMENTED {
+        state[i] = !state[i];
+    }
+
+    let mut a = *state;
+    for rc in RC {
+        round(&mut a, rc);
+    }
+    *state = a;
+
+    for i in COMPLEMENTED {
+        state[i] = !state[i];
+    }
+}
+
+/// `keccak-f[1600, 24]` with the [`KeccakFBackend::LaneComplementing`] backend, for use in
+/// the sponge constructions, e.g. `Keccak<KeccakFLaneComplementing>`.
+///
+/// [`KeccakFBackend::LaneComplementing`]: crate::KeccakFBackend::LaneComplementing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeccakFLaneComplementing;
+
+impl Permutation for KeccakFLaneComplementing {
+    fn permute(state: &mut [u64; WORDS]) {
+        keccakf_lane_complementing(state);
+    }
+}
+
+/// A single round over a lane-complemented state.
+#[inline(always)]
+fn round(a: &mut [u64; WORDS], rc: u64) {
+    let c0 = a[0] ^ a[5] ^ a[10] ^ a[15] ^ a[20];
+    let c1 = a[1] ^ a[6] ^ a[11] ^ a[16] ^ a[21];
+    let c2 = a[2] ^ a[7] ^ a[12] ^ a[17] ^ a[22];
+    let c3 = a[3] ^ a[8] ^ a[13] ^ a[18] ^ a[23];
+    let c4 = a[4] ^ a[9] ^ a[14] ^ a[19] ^ a[24];
+
+    let d0 = c4 ^ c1.rotate_left(1);
+    let d1 = c0 ^ c2.rotate_left(1);
+    let d2 = c1 ^ c3.rotate_left(1);
+    let d3 = c2 ^ c4.rotate_left(1);
+    let d4 = c3 ^ c0.rotate_left(1);
+
+    // Row 0
+    let b0 = a[0] ^ d0;
+    let b1 = (a[6] ^ d1).rotate_left(44);
+    let b2 = (a[12] ^ d2).rotate_left(43);
+    let b3 = (a[18] ^ d3).rotate_left(21);
+    let b4 = (a[24] ^ d4).rotate_left(14);
+    let e0 = b0 ^ (b1 | b2) ^ rc;
+    let e1 = b1 ^ (!b2 | b3);
+    let e2 = b2 ^ (b3 & b4);
+    let e3 = b3 ^ (b4 | b0);
+    let e4 = b4 ^ (b0 & b1);
+
+    // Row 1
+    let b0 = (a[3] ^ d3).rotate_left(28);
+    let b1 = (a[9] ^ d4).rotate_left(20);
+    let b2 = (a[10] ^ d0).rotate_left(3);
+    let b3 = (a[16] ^ d1).rotate_left(45);
+    let b4 = (a[22] ^ d2).rotate_left(61);
+    let e5 = b0 ^ (b1 | b2);
+    let e6 = b1 ^ (b2 & b3);
+    let e7 = b2 ^ (b3 | !b4);
+    let e8 = b3 ^ (b4 | b0);
+    let e9 = b4 ^ (b0 & b1);
+
+    // Row 2
+    let b0 = (a[1] ^ d1).rotate_left(1);
+    let b1 = (a[7] ^ d2).rotate_left(6);
+    let b2 = (a[13] ^ d3).rotate_left(25);
+    let b3 = (a[19] ^ d4).rotate_left(8);
+    let b4 = (a[20] ^ d0).rotate_left(18);
+    let e10 = b0 ^ (b1 | b2);
+    let e11 = b1 ^ (b2 & b3);
+    let e12 = b2 ^ (!b3 & b4);
+    let e13 = !b3 ^ (b4 | b0);
+    let e14 = b4 ^ (b0 & b1);
+
+    // Row 3
+    let b0 = (a[4] ^ d4).rotate_left(27);
+    let b1 = (a[5] ^ d0).rotate_left(36);
+    let b2 = (a[11] ^ d1).rotate_left(10);
+    let b3 = (a[17] ^ d2).rotate_left(15);
+    let b4 = (a[23] ^ d3).rotate_left(56);
+    let e15 = b0 ^ (b1 & b2);
+    let e16 = b1 ^ (b2 | b3);
+    let e17 = b2 ^ (!b3 | b4);
+    let e18 = !b3 ^ (b4 & b0);
+    let e19 = b4 ^ (b0 | b1);
+
+    // Row 4
+    let b0 = (a[2] ^ d2).rotate_left(62);
+    let b1 = (a[8] ^ d3).rotate_left(55);
+    let b2 = (a[14] ^ d4).rotate_left(39);
+    let b3 = (a[15] ^ d0).rotate_left(41);
+    let b4 = (a[21] ^ d1).rotate_left(2);
+    let e20 = b0 ^ (!b1 & b2);
+    let e21 = !b1 ^ (b2 | b3);
+    let e22 = b2 ^ (b3 & b4);
+    let e23 = b3 ^ (b4 | b0);
+    let e24 = b4 ^ (b0 & b1);
+
+    *a = [
+        e0, e1, e2, e3, e4, e5, e6, e7, e8, e9, e10, e11, e12, e13, e14, e15, e16, e17, e18, e19,
+        e20, e21, e22, e23, e24,
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{keccakf, Hasher, Keccak, KeccakF, KeccakFBackend};
+
+    #[test]
+    fn matches_reference() {
+        // xorshift64 states, including the all-zero and all-one edge cases.
+        let mut seed = 0x2545f4914f6cdd1du64;
+        let mut states = vec![[0u64; WORDS], [u64::MAX; WORDS]];
+        for _ in 0..64 {
+            let mut state = [0u64; WORDS];
+            for word in state.iter_mut() {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                *word = seed;
+            }
+            states.push(state);
+        }
+
+        for mut state in states {
+            let mut expected = state;
+            keccakf(&mut expected);
+            keccakf_lane_complementing(&mut state);
+            assert_eq!(state, expected);
+        }
+    }
+
+    #[test]
+    fn backend_selection() {
+        let mut expected = [0xa5u64; WORDS];
+        let mut state = expected;
+        KeccakFBackend::Reference.permute(&mut expected);
+        KeccakFBackend::LaneComplementing.permute(&mut state);
+        assert_eq!(state, expected);
+
+        // Both backends are used through the sponge in the same process.
+        fn hash<P: Permutation>(input: &[u8]) -> [u8; 32] {
+            let mut keccak = Keccak::<P>::with_permutation(256);
+            let mut output = [0u8; 32];
+            keccak.update(input);
+            keccak.finalize(&mut output);
+            output
+        }
+
+        for input in [&[][..], &[0xa5; 1000]] {
+            assert_eq!(
+                hash::<KeccakFLaneComplementing>(input),
+                hash::<KeccakF>(input)
+            );
+        }
+        assert_eq!(
+            hex::encode(hash::<KeccakFLaneComplementing>(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use crate::{Buffer, Permutation};
+use crate::inverse::inverse_keccak_function;
+use crate::{Permutation, WORDS};
 
 const ROUNDS: usize = 12;
 
@@ -18,11 +19,46 @@ const RC: [u64; ROUNDS] = [
 ];
 
 keccak_function!("`keccak-p[1600, 12]`", keccakp, ROUNDS, RC);
+inverse_keccak_function!("inverse `keccak-p[1600, 12]`", inverse_keccakp, ROUNDS, RC);
 
+#[derive(Clone)]
 pub struct KeccakP;
 
 impl Permutation for KeccakP {
-    fn execute(buffer: &mut Buffer) {
-        keccakp(buffer.words());
+    fn permute(state: &mut [u64; WORDS]) {
+        keccakp(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeccakP1600;
+
+    #[test]
+    fn inverse_random_states() {
+        let mut seed = 0x0123456789abcdefu64;
+        for _ in 0..16 {
+            let mut state = [0u64; WORDS];
+            for lane in state.iter_mut() {
+                // xorshift64
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                *lane = seed;
+            }
+
+            let mut permuted = state;
+            keccakp(&mut permuted);
+            assert_ne!(permuted, state);
+
+            // Keccak-p[1600, 12] are the last 12 rounds of Keccak-f[1600].
+            let mut expected = state;
+            <KeccakP1600<12> as crate::Permutation>::permute(&mut expected);
+            assert_eq!(permuted, expected);
+
+            inverse_keccakp(&mut permuted);
+            assert_eq!(permuted, state);
+        }
     }
 }
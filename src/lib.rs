@@ -53,16 +53,23 @@ const PI: [usize; 24] = [
 
 pub(crate) const WORDS: usize = 25;
 
+/// Generates a Keccak-f permutation over `$lane` lanes. Without the lane type it generates
+/// Keccak-f[1600]; narrower lanes use the rotation offsets modulo the lane width and the
+/// round constants truncated to it, as in FIPS 202.
 macro_rules! keccak_function {
     ($doc: expr, $name: ident, $rounds: expr, $rc: expr) => {
+        keccak_function!($doc, $name, u64, $rounds, $rc);
+    };
+    ($doc: expr, $name: ident, $lane: ty, $rounds: expr, $rc: expr) => {
         #[doc = $doc]
         #[allow(unused_assignments)]
         #[allow(non_upper_case_globals)]
-        pub fn $name(a: &mut [u64; $crate::WORDS]) {
+        #[allow(clippy::unnecessary_cast)]
+        pub fn $name(a: &mut [$lane; $crate::WORDS]) {
             use crunchy::unroll;
 
             for i in 0..$rounds {
-                let mut array: [u64; 5] = [0; 5];
+                let mut array: [$lane; 5] = [0; 5];
 
                 // Theta
                 unroll! {
@@ -92,7 +99,7 @@ macro_rules! keccak_function {
                 unroll! {
                     for x in 0..24 {
                         array[0] = a[$crate::PI[x]];
-                        a[$crate::PI[x]] = last.rotate_left($crate::RHO[x]);
+                        a[$crate::PI[x]] = last.rotate_left($crate::RHO[x] % <$lane>::BITS);
                         last = array[0];
                     }
                 }
@@ -117,23 +124,35 @@ macro_rules! keccak_function {
                 };
 
                 // Iota
-                a[0] ^= $rc[i];
+                a[0] ^= $rc[i] as $lane;
             }
         }
-    }
+    };
 }
 
+pub mod computation;
 mod expansion;
 pub mod fortuna;
+mod fp1600;
+pub mod header;
 mod inverse;
+pub mod mining;
+pub mod params;
+mod permutation;
 pub mod prime;
 pub mod sloth;
+mod small_keccak;
+pub mod vdf;
+pub mod version;
+
+pub use permutation::{KeccakF1600, KeccakP1600};
+pub use small_keccak::{keccakf200, keccakf400, keccakf800, Lane, SmallKeccak};
 
 #[cfg(feature = "k12")]
 mod keccakp;
 
 #[cfg(feature = "k12")]
-pub use keccakp::keccakp;
+pub use keccakp::{inverse_keccakp, keccakp};
 
 #[cfg(any(
     feature = "keccak",
@@ -155,7 +174,13 @@ mod keccakf;
     feature = "tuple_hash",
     feature = "parallel_hash"
 ))]
-pub use keccakf::keccakf;
+pub use keccakf::{inverse_keccakf, keccakf, KeccakF, KeccakFBackend};
+
+#[cfg(feature = "lane_complementing")]
+mod keccakf_lc;
+
+#[cfg(feature = "lane_complementing")]
+pub use keccakf_lc::{keccakf_lane_complementing, KeccakFLaneComplementing};
 
 #[cfg(feature = "k12")]
 mod k12;
@@ -167,7 +192,7 @@ pub use k12::{KangarooTwelve, KangarooTwelveXof};
 mod keccak;
 
 #[cfg(feature = "keccak")]
-pub use keccak::Keccak;
+pub use keccak::{Keccak, KeccakXof};
 
 #[cfg(feature = "shake")]
 mod shake;
@@ -229,6 +254,29 @@ pub trait Hasher {
     fn finalize(self, output: &mut [u8]);
 }
 
+/// A [`Hasher`] which can apply an extra number of permutations before squeezing the output.
+///
+/// It is used in the final stage of Keccak-prime.
+///
+/// # Example
+///
+/// ```
+/// # use keccak_prime::PenalizedHasher;
+/// #
+/// # fn foo<H: PenalizedHasher>(mut hasher: H) {
+/// let mut output = [0u8; 32];
+/// hasher.update(b"hello world");
+/// hasher.finalize_with_penalty_into(100, &mut output);
+/// # }
+/// ```
+///
+/// [`Hasher`]: trait.Hasher.html
+pub trait PenalizedHasher: Hasher {
+    /// Apply the permutation function `penalty` extra times, then pad and squeeze the state
+    /// to the output. If `penalty` equals 0, the result is the same as with [`Hasher::finalize`].
+    fn finalize_with_penalty_into(self, penalty: usize, output: &mut [u8]);
+}
+
 /// A trait used to convert [`Hasher`] into it's [`Xof`] counterpart.
 ///
 /// # Example
@@ -369,8 +417,16 @@ impl Buffer {
     }
 }
 
-trait Permutation {
-    fn execute(a: &mut Buffer);
+/// A permutation of the 1600-bit Keccak state.
+pub trait Permutation {
+    /// Applies the permutation to the state.
+    fn permute(state: &mut [u64; WORDS]);
+}
+
+/// A [`Permutation`] which can be inverted.
+pub trait InversePermutation: Permutation {
+    /// Applies the inverse permutation, undoing [`Permutation::permute`].
+    fn inverse(state: &mut [u64; WORDS]);
 }
 
 #[derive(Clone, Copy)]
@@ -404,7 +460,7 @@ impl<P: Permutation> KeccakState<P> {
 
     /// Call the permutation function.
     fn keccak(&mut self) {
-        P::execute(&mut self.buffer);
+        P::permute(self.buffer.words());
     }
 
     /// Absorb additional input. Can be called multiple times.
@@ -463,22 +519,31 @@ impl<P: Permutation> KeccakState<P> {
 
     /// Squeezes Keccak state into a 256-bit string.
     /// If `penalty` equals 0, the result will be the same as with the original Keccak function.
-    pub(crate) fn finalize_with_penalty(mut self, penalty: usize) -> [u8; 32] {
+    pub(crate) fn finalize_with_penalty(self, penalty: usize) -> [u8; 32] {
         let mut output = [0u8; 32];
 
-        // Apply permutation func repeatedly for a number of `penalty` times
-        for _i in 0..penalty {
-            self.keccak();
-        }
-
         // The permutation fn should run only once because len = 256 (32 * 8) is less than the
         // expected rate of 1088 bits. Hence, we'll take only the first 256 bits out of the
         // current state and discard everything else.
-        self.squeeze(&mut output);
+        self.finalize_with_penalty_into(penalty, &mut output);
 
         output
     }
 
+    /// Squeezes Keccak state into `output` of an arbitrary length after applying
+    /// the permutation function `penalty` extra times.
+    pub(crate) fn finalize_with_penalty_into(mut self, penalty: usize, output: &mut [u8]) {
+        self.penalize(penalty);
+        self.squeeze(output);
+    }
+
+    /// Apply permutation func repeatedly for a number of `penalty` times.
+    pub(crate) fn penalize(&mut self, penalty: usize) {
+        for _i in 0..penalty {
+            self.keccak();
+        }
+    }
+
     fn finalize(mut self, output: &mut [u8]) {
         self.squeeze(output);
     }
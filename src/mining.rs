@@ -0,0 +1,338 @@
+//! Nonce search for the Keccak-prime proof of work.
+
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+
+use num_bigint::BigUint;
+
+use crate::{
+    expansion::{expand_with_key, KeyDerivation, INPUT_HASH_SIZE, NONCE_SIZE},
+    params::PrimeParams,
+    prime::{prime_block, KeccakPrimeError},
+};
+
+/// Difficulty target that a Keccak-prime output has to meet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// The output must start with at least this number of zero bits.
+    LeadingZeros(u32),
+    /// The output, interpreted as a big-endian integer, must be less than or equal to this value.
+    BigEndian(Vec<u8>),
+}
+
+impl Target {
+    /// Checks whether `hash` meets the target.
+    pub fn is_met_by(&self, hash: &[u8]) -> bool {
+        match self {
+            Target::LeadingZeros(bits) => leading_zero_bits(hash) >= *bits,
+            Target::BigEndian(target) => {
+                BigUint::from_bytes_be(hash) <= BigUint::from_bytes_be(target)
+            }
+        }
+    }
+}
+
+/// Counts the number of leading zero bits in a byte string.
+fn leading_zero_bits(bytes: &[u8]) -> u32 {
+    let mut zeros = 0;
+    for byte in bytes {
+        zeros += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    zeros
+}
+
+/// Winning nonce together with its Keccak-prime output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    /// Nonce that meets the target.
+    pub nonce: [u8; NONCE_SIZE],
+    /// Keccak-prime output for `nonce`.
+    pub hash: Vec<u8>,
+}
+
+/// How a nonce search has ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MiningStatus {
+    /// A nonce meeting the target has been found.
+    Found(Solution),
+    /// No nonce in the range meets the target.
+    Exhausted,
+    /// The search has been cancelled.
+    Cancelled,
+}
+
+/// Result of a nonce search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MiningOutcome {
+    /// How the search has ended.
+    pub status: MiningStatus,
+    /// Number of Keccak-prime evaluations performed.
+    pub hashes_tried: u64,
+}
+
+/// Searches for a nonce for which the Keccak-prime output meets a difficulty target.
+pub struct Miner {
+    params: PrimeParams,
+    target: Target,
+    /// Key derivation with the block hashes already absorbed; only the nonce is left to add.
+    kdf: KeyDerivation,
+}
+
+impl Miner {
+    /// Creates a new miner for a block identified by `prev_hash` and `root_hash`.
+    pub fn new(
+        prev_hash: [u8; INPUT_HASH_SIZE],
+        root_hash: [u8; INPUT_HASH_SIZE],
+        params: PrimeParams,
+        target: Target,
+    ) -> Miner {
+        let mut kdf = KeyDerivation::new(params.kdf());
+        kdf.update(&prev_hash);
+        kdf.update(&root_hash);
+
+        Miner {
+            params,
+            target,
+            kdf,
+        }
+    }
+
+    /// Evaluates Keccak-prime for a single `nonce`.
+    pub fn hash(&self, nonce: u64) -> Result<Vec<u8>, KeccakPrimeError> {
+        let nonce = nonce.to_be_bytes();
+        let derived_key = self.kdf.clone().finalize(nonce);
+        let block = expand_with_key(&derived_key, nonce, self.params.expansion_size())?;
+        let (hash, _proof) = prime_block(&block, &self.params);
+        Ok(hash)
+    }
+
+    /// Tries nonces from `nonces` in ascending order and returns the first one meeting the target.
+    ///
+    /// The search stops early once `cancel` is set.
+    pub fn mine(
+        &self,
+        nonces: RangeInclusive<u64>,
+        cancel: &AtomicBool,
+    ) -> Result<MiningOutcome, KeccakPrimeError> {
+        let mut hashes_tried = 0;
+
+        for nonce in nonces {
+            if cancel.load(Ordering::Relaxed) {
+                return Ok(MiningOutcome {
+                    status: MiningStatus::Cancelled,
+                    hashes_tried,
+                });
+            }
+
+            let hash = self.hash(nonce)?;
+            hashes_tried += 1;
+
+            if self.target.is_met_by(&hash) {
+                return Ok(MiningOutcome {
+                    status: MiningStatus::Found(Solution {
+                        nonce: nonce.to_be_bytes(),
+                        hash,
+                    }),
+                    hashes_tried,
+                });
+            }
+        }
+
+        Ok(MiningOutcome {
+            status: MiningStatus::Exhausted,
+            hashes_tried,
+        })
+    }
+
+    /// Same as [`Miner::mine`], but splits `nonces` across `threads` worker threads.
+    ///
+    /// Worker `i` tries every `threads`-th nonce starting from `nonces.start() + i`. Once a
+    /// solution is found, workers stop as soon as they pass the lowest winning nonce seen so
+    /// far, so the returned solution is always the lowest winning nonce in the range, exactly
+    /// as with [`Miner::mine`].
+    pub fn mine_parallel(
+        &self,
+        threads: usize,
+        nonces: RangeInclusive<u64>,
+        cancel: &AtomicBool,
+    ) -> Result<MiningOutcome, KeccakPrimeError> {
+        let threads = threads.max(1);
+
+        // Lowest winning nonce found so far, `u64::MAX` if there's none yet.
+        let best = AtomicU64::new(u64::MAX);
+        // Stops all workers on cancellation or on a failure in any of them.
+        let stop = AtomicBool::new(false);
+        let hashes_tried = AtomicU64::new(0);
+
+        let results: Vec<_> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|worker| {
+                    let worker_nonces = nonces.clone().skip(worker).step_by(threads);
+                    let (best, stop, hashes_tried) = (&best, &stop, &hashes_tried);
+
+                    scope.spawn(move || {
+                        for nonce in worker_nonces {
+                            if nonce > best.load(Ordering::SeqCst) || stop.load(Ordering::SeqCst) {
+                                break;
+                            }
+                            if cancel.load(Ordering::Relaxed) {
+                                stop.store(true, Ordering::SeqCst);
+                                break;
+                            }
+
+                            let hash = match self.hash(nonce) {
+                                Ok(hash) => hash,
+                                Err(e) => {
+                                    stop.store(true, Ordering::SeqCst);
+                                    return Err(e);
+                                }
+                            };
+                            hashes_tried.fetch_add(1, Ordering::Relaxed);
+
+                            if self.target.is_met_by(&hash) {
+                                best.fetch_min(nonce, Ordering::SeqCst);
+                                return Ok(Some(Solution {
+                                    nonce: nonce.to_be_bytes(),
+                                    hash,
+                                }));
+                            }
+                        }
+                        Ok(None)
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("mining worker panicked"))
+                .collect()
+        });
+
+        let mut solutions = Vec::new();
+        for result in results {
+            solutions.extend(result?);
+        }
+        // Big-endian nonces compare the same way as their integer values.
+        let lowest = solutions.into_iter().min_by_key(|solution| solution.nonce);
+
+        let status = if cancel.load(Ordering::Relaxed) && stop.load(Ordering::SeqCst) {
+            MiningStatus::Cancelled
+        } else {
+            match lowest {
+                Some(solution) => MiningStatus::Found(solution),
+                None => MiningStatus::Exhausted,
+            }
+        };
+
+        Ok(MiningOutcome {
+            status,
+            hashes_tried: hashes_tried.load(Ordering::Relaxed),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::prime_with_params;
+
+    fn test_params() -> PrimeParams {
+        PrimeParams::builder()
+            .penalty(0)
+            .delay(1)
+            .vdf_iterations(1)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn targets() {
+        assert_eq!(leading_zero_bits(&[0, 0, 0x10, 0xff]), 19);
+        assert_eq!(leading_zero_bits(&[0, 0]), 16);
+        assert!(Target::LeadingZeros(3).is_met_by(&[0x1f, 0xff]));
+        assert!(!Target::LeadingZeros(4).is_met_by(&[0x1f, 0xff]));
+        assert!(Target::BigEndian(vec![0x1f, 0xff]).is_met_by(&[0x1f, 0xff]));
+        assert!(!Target::BigEndian(vec![0x1f, 0xfe]).is_met_by(&[0x1f, 0xff]));
+    }
+
+    #[test]
+    fn finds_first_winning_nonce() {
+        let (prev_hash, root_hash) = ([1u8; INPUT_HASH_SIZE], [2u8; INPUT_HASH_SIZE]);
+        let miner = Miner::new(prev_hash, root_hash, test_params(), Target::LeadingZeros(4));
+
+        let outcome = miner.mine(0..=1000, &AtomicBool::new(false)).unwrap();
+        let solution = match outcome.status {
+            MiningStatus::Found(solution) => solution,
+            status => panic!("unexpected status: {:?}", status),
+        };
+        let nonce = u64::from_be_bytes(solution.nonce);
+        assert_eq!(outcome.hashes_tried, nonce + 1);
+
+        // The hash must match the one computed by `prime` and no earlier nonce may win.
+        let hash = prime_with_params(prev_hash, root_hash, solution.nonce, &test_params()).unwrap();
+        assert_eq!(solution.hash, hash);
+        assert!(Target::LeadingZeros(4).is_met_by(&hash));
+        for earlier in 0..nonce {
+            assert!(!Target::LeadingZeros(4).is_met_by(&miner.hash(earlier).unwrap()));
+        }
+    }
+
+    #[test]
+    fn exhausted_and_cancelled() {
+        let miner = Miner::new(
+            [1; 32],
+            [2; 32],
+            test_params(),
+            Target::BigEndian(vec![0; 32]),
+        );
+
+        let outcome = miner.mine(5..=9, &AtomicBool::new(false)).unwrap();
+        assert_eq!(outcome.status, MiningStatus::Exhausted);
+        assert_eq!(outcome.hashes_tried, 5);
+
+        let outcome = miner.mine(0..=u64::MAX, &AtomicBool::new(true)).unwrap();
+        assert_eq!(outcome.status, MiningStatus::Cancelled);
+        assert_eq!(outcome.hashes_tried, 0);
+    }
+
+    #[test]
+    fn parallel_search_is_deterministic() {
+        let miner = Miner::new([1; 32], [2; 32], test_params(), Target::LeadingZeros(5));
+        let sequential = miner.mine(0..=1000, &AtomicBool::new(false)).unwrap();
+        assert!(matches!(sequential.status, MiningStatus::Found(_)));
+
+        for threads in [1, 2, 3, 8] {
+            let parallel = miner
+                .mine_parallel(threads, 0..=1000, &AtomicBool::new(false))
+                .unwrap();
+            assert_eq!(parallel.status, sequential.status);
+            assert!(parallel.hashes_tried >= sequential.hashes_tried);
+        }
+    }
+
+    #[test]
+    fn parallel_exhausted_and_cancelled() {
+        let miner = Miner::new(
+            [1; 32],
+            [2; 32],
+            test_params(),
+            Target::BigEndian(vec![0; 32]),
+        );
+
+        let outcome = miner
+            .mine_parallel(4, u64::MAX - 9..=u64::MAX, &AtomicBool::new(false))
+            .unwrap();
+        assert_eq!(outcome.status, MiningStatus::Exhausted);
+        assert_eq!(outcome.hashes_tried, 10);
+
+        let outcome = miner
+            .mine_parallel(4, 0..=u64::MAX, &AtomicBool::new(true))
+            .unwrap();
+        assert_eq!(outcome.status, MiningStatus::Cancelled);
+        assert_eq!(outcome.hashes_tried, 0);
+    }
+}
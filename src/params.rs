@@ -0,0 +1,328 @@
+//! Parameter sets for the Keccak-prime function.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::{expansion::INPUT_HASH_SIZE, vdf::VdfKind, WORDS};
+
+/// Width of the Keccak-f[1600] state, in bytes.
+const STATE_SIZE: usize = WORDS * 8;
+
+/// Rate and capacity of the final Keccak sponge.
+///
+/// Changing the sponge changes every Keccak-prime output, so the construction is versioned:
+/// existing chains keep [`SpongeVersion::Legacy`], and new chains use [`SpongeVersion::V1`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpongeVersion {
+    /// Rate of 166 bytes and capacity of 272 bits.
+    ///
+    /// The original implementation constructed the sponge as `Keccak::new(1088 / 8)`,
+    /// which treats 136 as a security level in bits rather than as a rate in bytes.
+    Legacy,
+    /// Rate of 136 bytes (1088 bits) and capacity of 512 bits, matching the 136-byte output
+    /// of the expansion function.
+    V1,
+}
+
+impl SpongeVersion {
+    /// Rate of the sponge, in bytes.
+    pub fn rate(&self) -> usize {
+        match self {
+            SpongeVersion::Legacy => 166,
+            SpongeVersion::V1 => 136,
+        }
+    }
+
+    /// Capacity of the sponge, in bits.
+    pub fn capacity(&self) -> usize {
+        (STATE_SIZE - self.rate()) * 8
+    }
+}
+
+/// Derivation of the AES key used in the expansion function from the block hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpansionKdf {
+    /// XOR of the previous block hash and the Merkle root hash.
+    ///
+    /// Kept for existing chains only: swapping the hashes gives the same key, and equal hashes
+    /// give an all-zero key.
+    Xor = 0,
+    /// cSHAKE256 with a customization string over a TupleHash-style encoding of the previous
+    /// block hash, the Merkle root hash and the nonce. Requires the `cshake` feature.
+    #[cfg(feature = "cshake")]
+    CShake256 = 1,
+}
+
+/// Validated set of parameters for the Keccak-prime function.
+///
+/// Use [`PrimeParams::builder`] to construct a custom set, or one of the named presets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimeParams {
+    expansion_size: usize,
+    rate: usize,
+    output_len: usize,
+    penalty: usize,
+    delay: u64,
+    vdf_iterations: usize,
+    vdf: VdfKind,
+    kdf: ExpansionKdf,
+}
+
+impl PrimeParams {
+    /// Parameters used by the Zenotta main network.
+    ///
+    /// The expansion function outputs 136 bytes (1088 bits), and the final Keccak uses
+    /// the [`SpongeVersion::Legacy`] rate of 166 bytes and capacity of 272 bits.
+    pub fn zenotta_mainnet() -> PrimeParams {
+        PrimeParams {
+            expansion_size: 136,
+            rate: SpongeVersion::Legacy.rate(),
+            output_len: INPUT_HASH_SIZE,
+            penalty: 100,
+            delay: 100,
+            vdf_iterations: 10,
+            vdf: VdfKind::Sloth,
+            kdf: ExpansionKdf::Xor,
+        }
+    }
+
+    /// Parameters for new chains: the [`zenotta_mainnet`] parameters with the
+    /// [`SpongeVersion::V1`] rate of 136 bytes and capacity of 512 bits.
+    ///
+    /// [`zenotta_mainnet`]: PrimeParams::zenotta_mainnet
+    pub fn zenotta_v1() -> PrimeParams {
+        PrimeParams {
+            rate: SpongeVersion::V1.rate(),
+            ..PrimeParams::zenotta_mainnet()
+        }
+    }
+
+    /// Creates a new [`PrimeParamsBuilder`] initialised with the [`zenotta_mainnet`] parameters.
+    ///
+    /// [`zenotta_mainnet`]: PrimeParams::zenotta_mainnet
+    pub fn builder() -> PrimeParamsBuilder {
+        PrimeParamsBuilder {
+            params: PrimeParams::zenotta_mainnet(),
+        }
+    }
+
+    /// Size of the expansion function output, in bytes.
+    pub fn expansion_size(&self) -> usize {
+        self.expansion_size
+    }
+
+    /// Rate of the final Keccak sponge, in bytes.
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    /// Capacity of the final Keccak sponge, in bits.
+    pub fn capacity(&self) -> usize {
+        (STATE_SIZE - self.rate) * 8
+    }
+
+    /// Length of the Keccak-prime output, in bytes.
+    pub fn output_len(&self) -> usize {
+        self.output_len
+    }
+
+    /// Number of extra Keccak permutations applied before squeezing.
+    pub fn penalty(&self) -> usize {
+        self.penalty
+    }
+
+    /// Delay parameter used in the VDF function.
+    pub fn delay(&self) -> u64 {
+        self.delay
+    }
+
+    /// Number of chained VDF iterations.
+    pub fn vdf_iterations(&self) -> usize {
+        self.vdf_iterations
+    }
+
+    /// VDF used in the chain.
+    pub fn vdf(&self) -> VdfKind {
+        self.vdf
+    }
+
+    /// Key derivation used in the expansion function.
+    pub fn kdf(&self) -> ExpansionKdf {
+        self.kdf
+    }
+}
+
+/// Builder for [`PrimeParams`].
+#[derive(Debug, Clone)]
+pub struct PrimeParamsBuilder {
+    params: PrimeParams,
+}
+
+impl PrimeParamsBuilder {
+    /// Sets the size of the expansion function output, in bytes.
+    pub fn expansion_size(mut self, expansion_size: usize) -> Self {
+        self.params.expansion_size = expansion_size;
+        self
+    }
+
+    /// Sets the rate of the final Keccak sponge, in bytes.
+    /// The capacity is the remainder of the 1600-bit state.
+    pub fn rate(mut self, rate: usize) -> Self {
+        self.params.rate = rate;
+        self
+    }
+
+    /// Sets the rate and capacity of the final Keccak sponge to one of the versioned
+    /// constructions.
+    pub fn sponge(mut self, sponge: SpongeVersion) -> Self {
+        self.params.rate = sponge.rate();
+        self
+    }
+
+    /// Sets the length of the Keccak-prime output, in bytes.
+    pub fn output_len(mut self, output_len: usize) -> Self {
+        self.params.output_len = output_len;
+        self
+    }
+
+    /// Sets the number of extra Keccak permutations applied before squeezing.
+    pub fn penalty(mut self, penalty: usize) -> Self {
+        self.params.penalty = penalty;
+        self
+    }
+
+    /// Sets the delay parameter used in the VDF function.
+    pub fn delay(mut self, delay: u64) -> Self {
+        self.params.delay = delay;
+        self
+    }
+
+    /// Sets the number of chained VDF iterations.
+    pub fn vdf_iterations(mut self, vdf_iterations: usize) -> Self {
+        self.params.vdf_iterations = vdf_iterations;
+        self
+    }
+
+    /// Sets the VDF used in the chain.
+    pub fn vdf(mut self, vdf: VdfKind) -> Self {
+        self.params.vdf = vdf;
+        self
+    }
+
+    /// Sets the key derivation used in the expansion function.
+    pub fn kdf(mut self, kdf: ExpansionKdf) -> Self {
+        self.params.kdf = kdf;
+        self
+    }
+
+    /// Validates the parameters and builds [`PrimeParams`].
+    pub fn build(self) -> Result<PrimeParams, PrimeParamsError> {
+        let params = self.params;
+
+        if params.rate == 0 {
+            return Err(PrimeParamsError::ZeroRate);
+        }
+        if params.rate >= STATE_SIZE {
+            return Err(PrimeParamsError::RateTooLarge(params.rate));
+        }
+        if params.expansion_size == 0 {
+            return Err(PrimeParamsError::ZeroExpansionSize);
+        }
+        // The expanded block is used as the VDF input, so it has to stay below the Sloth modulus.
+        if params.expansion_size >= STATE_SIZE {
+            return Err(PrimeParamsError::ExpansionTooLarge(params.expansion_size));
+        }
+        if params.output_len == 0 {
+            return Err(PrimeParamsError::ZeroOutputLength);
+        }
+
+        Ok(params)
+    }
+}
+
+/// Invalid combination of Keccak-prime parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimeParamsError {
+    /// The final Keccak rate is zero.
+    ZeroRate,
+    /// The final Keccak rate leaves no room for the capacity.
+    RateTooLarge(usize),
+    /// The expansion function output size is zero.
+    ZeroExpansionSize,
+    /// The expansion function output doesn't fit into the VDF modulus.
+    ExpansionTooLarge(usize),
+    /// The requested output length is zero.
+    ZeroOutputLength,
+}
+
+impl fmt::Display for PrimeParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrimeParamsError::ZeroRate => write!(f, "rate cannot be equal 0"),
+            PrimeParamsError::RateTooLarge(rate) => write!(
+                f,
+                "rate of {} bytes must be below the state size of {} bytes",
+                rate, STATE_SIZE
+            ),
+            PrimeParamsError::ZeroExpansionSize => write!(f, "expansion size cannot be equal 0"),
+            PrimeParamsError::ExpansionTooLarge(size) => write!(
+                f,
+                "expansion size of {} bytes must be below {} bytes",
+                size, STATE_SIZE
+            ),
+            PrimeParamsError::ZeroOutputLength => write!(f, "output length cannot be equal 0"),
+        }
+    }
+}
+
+impl Error for PrimeParamsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mainnet_preset_is_valid() {
+        let params = PrimeParams::builder().build().unwrap();
+        assert_eq!(params, PrimeParams::zenotta_mainnet());
+        assert_eq!(params.rate() * 8 + params.capacity(), 1600);
+    }
+
+    #[test]
+    fn sponge_versions() {
+        let legacy = PrimeParams::zenotta_mainnet();
+        assert_eq!((legacy.rate(), legacy.capacity()), (166, 272));
+
+        let v1 = PrimeParams::builder()
+            .sponge(SpongeVersion::V1)
+            .build()
+            .unwrap();
+        assert_eq!(v1, PrimeParams::zenotta_v1());
+        assert_eq!((v1.rate(), v1.capacity()), (136, 512));
+        assert_eq!(v1.rate(), v1.expansion_size());
+    }
+
+    #[test]
+    fn invalid_combinations() {
+        assert_eq!(
+            PrimeParams::builder().rate(0).build(),
+            Err(PrimeParamsError::ZeroRate)
+        );
+        assert_eq!(
+            PrimeParams::builder().rate(200).build(),
+            Err(PrimeParamsError::RateTooLarge(200))
+        );
+        assert_eq!(
+            PrimeParams::builder().expansion_size(0).build(),
+            Err(PrimeParamsError::ZeroExpansionSize)
+        );
+        assert_eq!(
+            PrimeParams::builder().expansion_size(256).build(),
+            Err(PrimeParamsError::ExpansionTooLarge(256))
+        );
+        assert_eq!(
+            PrimeParams::builder().output_len(0).build(),
+            Err(PrimeParamsError::ZeroOutputLength)
+        );
+    }
+}
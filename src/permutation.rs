@@ -0,0 +1,154 @@
+//! Round-reduced Keccak permutations with their inverses.
+
+use crate::{keccakf::RC, InversePermutation, Permutation, WORDS};
+
+/// Single Keccak-f[1600] rounds without the iota step. Round constants are applied
+/// separately, so that the number of rounds can be chosen freely.
+mod round {
+    use crate::inverse::inverse_keccak_function;
+
+    keccak_function!(
+        "`keccak-f[1600]` round without iota",
+        keccakf_round,
+        1,
+        [0u64]
+    );
+    inverse_keccak_function!(
+        "inverse `keccak-f[1600]` round without iota",
+        inverse_keccakf_round,
+        1,
+        [0u64]
+    );
+}
+
+/// Applies one Keccak-f[1600] round per round constant.
+pub(crate) fn keccak_rounds(a: &mut [u64; WORDS], round_constants: &[u64]) {
+    for rc in round_constants {
+        round::keccakf_round(a);
+        a[0] ^= rc;
+    }
+}
+
+/// Inverse of [`keccak_rounds`] with the same round constants.
+pub(crate) fn inverse_keccak_rounds(a: &mut [u64; WORDS], round_constants: &[u64]) {
+    for rc in round_constants.iter().rev() {
+        a[0] ^= rc;
+        round::inverse_keccakf_round(a);
+    }
+}
+
+/// Keccak-f[1600] reduced to its first `R` rounds, where `R` is between 1 and 24.
+///
+/// `KeccakF1600<24>` is the full [`keccakf`](crate::keccakf) permutation.
+///
+/// # Example
+///
+/// ```
+/// # use keccak_prime::{InversePermutation, KeccakF1600, Permutation};
+/// let mut state = [0u64; 25];
+/// KeccakF1600::<3>::permute(&mut state);
+/// KeccakF1600::<3>::inverse(&mut state);
+/// assert_eq!(state, [0u64; 25]);
+/// ```
+///
+/// Invalid round counts are rejected at compile time:
+///
+/// ```compile_fail
+/// # use keccak_prime::{KeccakF1600, Permutation};
+/// KeccakF1600::<25>::permute(&mut [0u64; 25]);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeccakF1600<const R: usize>;
+
+/// Keccak-p[1600, R] as defined in FIPS 202: the last `R` rounds of Keccak-f[1600],
+/// where `R` is between 1 and 24.
+///
+/// `KeccakP1600<12>` is the permutation used in KangarooTwelve.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeccakP1600<const R: usize>;
+
+impl<const R: usize> KeccakF1600<R> {
+    const ROUNDS: () = assert!(R >= 1 && R <= RC.len(), "number of rounds must be 1 to 24");
+
+    fn round_constants() -> &'static [u64] {
+        let () = Self::ROUNDS;
+        &RC[..R]
+    }
+}
+
+impl<const R: usize> KeccakP1600<R> {
+    const ROUNDS: () = assert!(R >= 1 && R <= RC.len(), "number of rounds must be 1 to 24");
+
+    fn round_constants() -> &'static [u64] {
+        let () = Self::ROUNDS;
+        &RC[RC.len() - R..]
+    }
+}
+
+impl<const R: usize> Permutation for KeccakF1600<R> {
+    fn permute(state: &mut [u64; WORDS]) {
+        keccak_rounds(state, Self::round_constants());
+    }
+}
+
+impl<const R: usize> InversePermutation for KeccakF1600<R> {
+    fn inverse(state: &mut [u64; WORDS]) {
+        inverse_keccak_rounds(state, Self::round_constants());
+    }
+}
+
+impl<const R: usize> Permutation for KeccakP1600<R> {
+    fn permute(state: &mut [u64; WORDS]) {
+        keccak_rounds(state, Self::round_constants());
+    }
+}
+
+impl<const R: usize> InversePermutation for KeccakP1600<R> {
+    fn inverse(state: &mut [u64; WORDS]) {
+        inverse_keccak_rounds(state, Self::round_constants());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keccakf;
+
+    fn test_state() -> [u64; WORDS] {
+        let mut state = [0u64; WORDS];
+        for (i, word) in state.iter_mut().enumerate() {
+            *word = (i as u64).wrapping_mul(0x9e3779b97f4a7c15);
+        }
+        state
+    }
+
+    fn round_trip<P: InversePermutation>() {
+        let mut state = test_state();
+        P::permute(&mut state);
+        assert_ne!(state, test_state());
+        P::inverse(&mut state);
+        assert_eq!(state, test_state());
+    }
+
+    #[test]
+    fn full_rounds() {
+        let mut expected = test_state();
+        keccakf(&mut expected);
+
+        let mut state = test_state();
+        KeccakF1600::<24>::permute(&mut state);
+        assert_eq!(state, expected);
+
+        let mut state = test_state();
+        KeccakP1600::<24>::permute(&mut state);
+        assert_eq!(state, expected);
+    }
+
+    #[test]
+    fn inverses() {
+        round_trip::<KeccakF1600<1>>();
+        round_trip::<KeccakF1600<3>>();
+        round_trip::<KeccakP1600<1>>();
+        round_trip::<KeccakP1600<12>>();
+    }
+}
@@ -1,20 +1,21 @@
 //! Implements the Keccak-prime function.
 
 use crate::{
-    expansion::{expand, INPUT_HASH_SIZE, NONCE_SIZE},
-    keccak::Keccak,
-    sloth, Hasher,
+    expansion::{expand, expand_with_key, KeyDerivation, INPUT_HASH_SIZE, NONCE_SIZE},
+    keccak::{Keccak, KeccakXof},
+    params::{ExpansionKdf, PrimeParams, PrimeParamsError},
+    vdf::{Vdf, VdfKind, VdfProof},
+    Hasher, PenalizedHasher,
 };
 use num_bigint::BigUint;
 use std::error::Error;
 use std::fmt;
 
-/// Size of output for the expansion function.
-/// Can be changed into a parameter for `prime` if needed.
-const EXPANSION_OUTPUT_SIZE: usize = 136; // 1088 bits
-
 /// Keccak-prime function.
 ///
+/// Uses the [`PrimeParams::zenotta_mainnet`] parameters with the provided
+/// `penalty`, `delay` and `vdf_iterations`.
+///
 /// ### Arguments
 /// - `prev_hash`: previous block hash.
 /// - `root_hash`: Merkle root hash.
@@ -30,21 +31,293 @@ pub fn prime(
     delay: u64,
     vdf_iterations: usize,
 ) -> Result<[u8; INPUT_HASH_SIZE], KeccakPrimeError> {
+    let params = PrimeParams::builder()
+        .penalty(penalty)
+        .delay(delay)
+        .vdf_iterations(vdf_iterations)
+        .build()?;
+
+    let mut keccak_prime = KeccakPrime::with_block_hashes(params, nonce);
+    keccak_prime.update(&prev_hash);
+    keccak_prime.update(&root_hash);
+
+    let mut output = [0u8; INPUT_HASH_SIZE];
+    keccak_prime.finalize(&mut output);
+    Ok(output)
+}
+
+/// Keccak-prime over an arbitrary serialized block header.
+///
+/// The header is absorbed with [`Hasher::update`], while the nonce is supplied separately,
+/// since it selects the expansion function output. The header goes through the key
+/// derivation of the expansion function, and then the same VDF chain and penalized Keccak
+/// are applied as in [`prime_with_params`]. A header made of `prev_hash` and `root_hash`
+/// gives the same output as [`prime_with_params`].
+///
+/// The header must be absorbed with the [`ExpansionKdf::CShake256`] key derivation:
+/// [`ExpansionKdf::Xor`] can't tell headers of other layouts apart, so it is rejected.
+///
+/// [`Hasher::finalize`] fills an output of any length, the first `params.output_len()` bytes
+/// of which are the Keccak-prime hash.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "cshake")] {
+/// # use keccak_prime::{params::{ExpansionKdf, PrimeParams}, prime::KeccakPrime, Hasher};
+/// let params = PrimeParams::builder()
+///     .penalty(10)
+///     .delay(10)
+///     .vdf_iterations(1)
+///     .kdf(ExpansionKdf::CShake256)
+///     .build()
+///     .unwrap();
+///
+/// let mut keccak_prime = KeccakPrime::new(params, [3; 8]).unwrap();
+/// keccak_prime.update(b"version 1");
+/// keccak_prime.update(&1_600_000_000u64.to_be_bytes());
+/// let mut output = [0u8; 32];
+/// keccak_prime.finalize(&mut output);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct KeccakPrime {
+    params: PrimeParams,
+    nonce: [u8; NONCE_SIZE],
+    kdf: KeyDerivation,
+}
+
+impl KeccakPrime {
+    /// Creates a new [`KeccakPrime`] hasher for the `nonce`.
+    ///
+    /// Fails with [`KeccakPrimeError::UnsupportedKdf`] if `params` use [`ExpansionKdf::Xor`].
+    pub fn new(
+        params: PrimeParams,
+        nonce: [u8; NONCE_SIZE],
+    ) -> Result<KeccakPrime, KeccakPrimeError> {
+        if params.kdf() == ExpansionKdf::Xor {
+            return Err(KeccakPrimeError::UnsupportedKdf(params.kdf()));
+        }
+        Ok(KeccakPrime::with_block_hashes(params, nonce))
+    }
+
+    /// Same as [`KeccakPrime::new`], but accepts any key derivation. Only for headers made
+    /// of `prev_hash` and `root_hash`, which is what [`ExpansionKdf::Xor`] is defined on.
+    pub(crate) fn with_block_hashes(params: PrimeParams, nonce: [u8; NONCE_SIZE]) -> KeccakPrime {
+        KeccakPrime {
+            kdf: KeyDerivation::new(params.kdf()),
+            params,
+            nonce,
+        }
+    }
+
+    /// Computes the Keccak-prime hash of `params.output_len()` bytes together with
+    /// a [`PrimeProof`].
+    pub fn finalize_with_proof(self) -> Result<(Vec<u8>, PrimeProof), KeccakPrimeError> {
+        let block = self.expand()?;
+        Ok(prime_block(&block, &self.params))
+    }
+
+    fn expand(&self) -> Result<Vec<u8>, KeccakPrimeError> {
+        let derived_key = self.kdf.clone().finalize(self.nonce);
+        expand_with_key(&derived_key, self.nonce, self.params.expansion_size())
+    }
+}
+
+impl Hasher for KeccakPrime {
+    fn update(&mut self, input: &[u8]) {
+        self.kdf.update(input);
+    }
+
+    fn finalize(self, output: &mut [u8]) {
+        // The expansion only encrypts 16-byte blocks, which is always within the AES-GCM-SIV
+        // plaintext limit.
+        let block = self
+            .expand()
+            .expect("encryption of 16-byte blocks doesn't fail");
+        let (vdf_output, _proof) = solve_chain(&block, &self.params);
+
+        let mut keccak = Keccak::with_rate_capacity(self.params.rate(), self.params.capacity());
+        keccak.update(&vdf_output.to_bytes_be());
+        keccak.finalize_with_penalty_into(self.params.penalty(), output);
+    }
+}
+
+/// Keccak-prime function with a custom parameter set.
+///
+/// Returns `params.output_len()` bytes.
+pub fn prime_with_params(
+    prev_hash: [u8; INPUT_HASH_SIZE],
+    root_hash: [u8; INPUT_HASH_SIZE],
+    nonce: [u8; NONCE_SIZE],
+    params: &PrimeParams,
+) -> Result<Vec<u8>, KeccakPrimeError> {
+    let (hash, _proof) = prime_with_proof(prev_hash, root_hash, nonce, params)?;
+    Ok(hash)
+}
+
+/// Keccak-prime function with a custom hasher used in the final stage.
+///
+/// The `hasher` replaces the final Keccak sponge, so `params.rate()` is ignored:
+/// the rate is determined by the hasher itself.
+/// Returns `params.output_len()` bytes.
+pub fn prime_with<H: PenalizedHasher>(
+    hasher: H,
+    prev_hash: [u8; INPUT_HASH_SIZE],
+    root_hash: [u8; INPUT_HASH_SIZE],
+    nonce: [u8; NONCE_SIZE],
+    params: &PrimeParams,
+) -> Result<Vec<u8>, KeccakPrimeError> {
+    let block = expand(
+        prev_hash,
+        root_hash,
+        nonce,
+        params.expansion_size(),
+        params.kdf(),
+    )?;
+    let (vdf_output, _proof) = solve_chain(&block, params);
+    Ok(finalize(hasher, &vdf_output, params))
+}
+
+/// Keccak-prime function with an output of arbitrary length.
+///
+/// Returns an [`Xof`](crate::Xof) handle to the final Keccak state after the penalty
+/// permutations have been applied. The first `params.output_len()` squeezed bytes are equal
+/// to the output of [`prime_with_params`].
+pub fn prime_xof(
+    prev_hash: [u8; INPUT_HASH_SIZE],
+    root_hash: [u8; INPUT_HASH_SIZE],
+    nonce: [u8; NONCE_SIZE],
+    params: &PrimeParams,
+) -> Result<KeccakXof, KeccakPrimeError> {
+    let block = expand(
+        prev_hash,
+        root_hash,
+        nonce,
+        params.expansion_size(),
+        params.kdf(),
+    )?;
+    let (vdf_output, _proof) = solve_chain(&block, params);
+
+    let mut keccak = Keccak::with_rate_capacity(params.rate(), params.capacity());
+    keccak.update(&vdf_output.to_bytes_be());
+    Ok(keccak.into_xof_with_penalty(params.penalty()))
+}
+
+/// Keccak-prime function which also returns a [`PrimeProof`] for the computed hash.
+pub fn prime_with_proof(
+    prev_hash: [u8; INPUT_HASH_SIZE],
+    root_hash: [u8; INPUT_HASH_SIZE],
+    nonce: [u8; NONCE_SIZE],
+    params: &PrimeParams,
+) -> Result<(Vec<u8>, PrimeProof), KeccakPrimeError> {
     // Expand the block.
-    let block = expand(prev_hash, root_hash, nonce, EXPANSION_OUTPUT_SIZE)?;
+    let block = expand(
+        prev_hash,
+        root_hash,
+        nonce,
+        params.expansion_size(),
+        params.kdf(),
+    )?;
+    Ok(prime_block(&block, params))
+}
+
+/// Runs the VDF chain and the final Keccak over an already expanded `block`.
+pub(crate) fn prime_block(block: &[u8], params: &PrimeParams) -> (Vec<u8>, PrimeProof) {
+    let (vdf_output, proof) = solve_chain(block, params);
+    let hash = finalize(
+        Keccak::with_rate_capacity(params.rate(), params.capacity()),
+        &vdf_output,
+        params,
+    );
+    (hash, proof)
+}
+
+/// Executes a chain of VDFs over the expanded `block`.
+/// Returns the chain output and the proof with every intermediate witness.
+fn solve_chain(block: &[u8], params: &PrimeParams) -> (BigUint, PrimeProof) {
+    let mut proof = PrimeProof {
+        witnesses: Vec::with_capacity(params.vdf_iterations()),
+        vdf_proofs: Vec::with_capacity(params.vdf_iterations()),
+    };
+    let mut vdf_output = BigUint::from_bytes_be(block);
+    for _i in 0..params.vdf_iterations() {
+        let (output, vdf_proof) = params.vdf().eval(&vdf_output, params.delay());
+        vdf_output = output;
+        proof.witnesses.push(vdf_output.clone());
+        proof.vdf_proofs.push(vdf_proof);
+    }
+    (vdf_output, proof)
+}
+
+/// Verifies that `hash` is a Keccak-prime output for the provided inputs.
+///
+/// Instead of re-running the sequential VDF chain, every link of the chain is checked with
+/// [`Vdf::verify`], which is cheaper than the evaluation.
+///
+/// ## Returns
+/// - `true` if the proof is valid and leads to `hash`.
+pub fn verify(
+    prev_hash: [u8; INPUT_HASH_SIZE],
+    root_hash: [u8; INPUT_HASH_SIZE],
+    nonce: [u8; NONCE_SIZE],
+    params: &PrimeParams,
+    hash: &[u8],
+    proof: &PrimeProof,
+) -> Result<bool, KeccakPrimeError> {
+    if proof.witnesses.len() != params.vdf_iterations()
+        || proof.vdf_proofs.len() != params.vdf_iterations()
+    {
+        return Ok(false);
+    }
 
-    // Execute a chain of VDFs.
-    let mut vdf_output = BigUint::from_bytes_be(&block);
-    for _i in 0..vdf_iterations {
-        vdf_output = sloth::solve(vdf_output, delay);
+    let block = expand(
+        prev_hash,
+        root_hash,
+        nonce,
+        params.expansion_size(),
+        params.kdf(),
+    )?;
+
+    // Each witness must be a valid VDF output for the previous link of the chain.
+    let mut vdf_input = BigUint::from_bytes_be(&block);
+    for (witness, vdf_proof) in proof.witnesses.iter().zip(&proof.vdf_proofs) {
+        if !params
+            .vdf()
+            .verify(&vdf_input, params.delay(), witness, vdf_proof)
+        {
+            return Ok(false);
+        }
+        vdf_input = witness.clone();
     }
 
-    let vdf_output_bytes = vdf_output.to_bytes_be();
+    let keccak = Keccak::with_rate_capacity(params.rate(), params.capacity());
+    Ok(finalize(keccak, &vdf_input, params) == hash)
+}
 
-    // Construct a Keccak function with rate=1088 and capacity=512.
-    let mut keccak = Keccak::new(1088 / 8);
-    keccak.update(&vdf_output_bytes);
-    Ok(keccak.finalize_with_penalty(penalty))
+/// Applies the final penalized hasher to the VDF chain output.
+pub(crate) fn finalize<H: PenalizedHasher>(
+    mut hasher: H,
+    vdf_output: &BigUint,
+    params: &PrimeParams,
+) -> Vec<u8> {
+    hasher.update(&vdf_output.to_bytes_be());
+
+    let mut output = vec![0u8; params.output_len()];
+    hasher.finalize_with_penalty_into(params.penalty(), &mut output);
+    output
+}
+
+/// Proof of a Keccak-prime evaluation.
+///
+/// Holds the intermediate VDF outputs for each of the `vdf_iterations`, so that
+/// a validator can check the result without solving the VDF chain again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimeProof {
+    /// VDF outputs, in the order they were computed.
+    pub witnesses: Vec<BigUint>,
+    /// VDF proofs for each of the `witnesses`.
+    pub vdf_proofs: Vec<VdfProof>,
 }
 
 /// Keccak-prime error.
@@ -52,6 +325,14 @@ pub fn prime(
 pub enum KeccakPrimeError {
     /// Opaque AES function failure.
     AesError(aes_gcm_siv::aead::Error),
+    /// Invalid Keccak-prime parameters.
+    InvalidParams(PrimeParamsError),
+    /// Checkpoint bytes can't be decoded.
+    MalformedCheckpoint,
+    /// The operation doesn't support the selected VDF.
+    UnsupportedVdf(VdfKind),
+    /// The operation doesn't support the selected key derivation.
+    UnsupportedKdf(ExpansionKdf),
 }
 
 impl From<aes_gcm_siv::aead::Error> for KeccakPrimeError {
@@ -60,10 +341,22 @@ impl From<aes_gcm_siv::aead::Error> for KeccakPrimeError {
     }
 }
 
+impl From<PrimeParamsError> for KeccakPrimeError {
+    fn from(e: PrimeParamsError) -> Self {
+        Self::InvalidParams(e)
+    }
+}
+
 impl fmt::Display for KeccakPrimeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             KeccakPrimeError::AesError(e) => write!(f, "AES error: {}", e),
+            KeccakPrimeError::InvalidParams(e) => write!(f, "Invalid parameters: {}", e),
+            KeccakPrimeError::MalformedCheckpoint => write!(f, "Malformed checkpoint"),
+            KeccakPrimeError::UnsupportedVdf(vdf) => write!(f, "Unsupported VDF: {:?}", vdf),
+            KeccakPrimeError::UnsupportedKdf(kdf) => {
+                write!(f, "Unsupported key derivation: {:?}", kdf)
+            }
         }
     }
 }
@@ -72,14 +365,25 @@ impl Error for KeccakPrimeError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             KeccakPrimeError::AesError(_err) => None, // aes_gcm_siv::Error doesn't implement the Error trait
+            KeccakPrimeError::InvalidParams(err) => Some(err),
+            KeccakPrimeError::MalformedCheckpoint => None,
+            KeccakPrimeError::UnsupportedVdf(_) => None,
+            KeccakPrimeError::UnsupportedKdf(_) => None,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::prime;
+    use super::{
+        prime, prime_with, prime_with_params, prime_with_proof, prime_xof, verify, KeccakPrime,
+        KeccakPrimeError,
+    };
     use crate::expansion::{INPUT_HASH_SIZE, NONCE_SIZE};
+    use crate::params::{ExpansionKdf, PrimeParams, SpongeVersion};
+    use crate::vdf::{VdfKind, VdfProof};
+    use crate::{keccak::Keccak, Sha3, Shake, Xof};
+    use num_bigint::BigUint;
 
     #[test]
     fn keccak_prime_test() {
@@ -87,7 +391,286 @@ mod tests {
         let root_hash = [2u8; INPUT_HASH_SIZE];
         let nonce = [3u8; NONCE_SIZE];
 
-        dbg!(prime(prev_hash, root_hash, nonce, 100, 100, 10)
-            .expect("Failed to execute Keccak-prime"));
+        let hash = prime(prev_hash, root_hash, nonce, 100, 100, 10)
+            .expect("Failed to execute Keccak-prime");
+
+        // Frozen output of the main network parameters, `PrimeVersion::V0`.
+        assert_eq!(
+            hex::encode(hash),
+            "cff4238e7bb74ad58ef43fe5d12948241e01be4269db8039812d6d355470a864"
+        );
+    }
+
+    #[test]
+    fn verify_proof() {
+        let prev_hash = [1u8; INPUT_HASH_SIZE];
+        let root_hash = [2u8; INPUT_HASH_SIZE];
+        let nonce = [3u8; NONCE_SIZE];
+        let params = test_params(3);
+
+        let (hash, proof) = prime_with_proof(prev_hash, root_hash, nonce, &params).unwrap();
+        assert_eq!(proof.witnesses.len(), 3);
+        assert_eq!(hash, prime(prev_hash, root_hash, nonce, 10, 10, 3).unwrap());
+        assert!(verify(prev_hash, root_hash, nonce, &params, &hash, &proof).unwrap());
+
+        // A different hash, nonce or number of iterations must be rejected.
+        let mut wrong_hash = hash.clone();
+        wrong_hash[0] ^= 1;
+        assert!(!verify(prev_hash, root_hash, nonce, &params, &wrong_hash, &proof).unwrap());
+        assert!(!verify(
+            prev_hash,
+            root_hash,
+            [4; NONCE_SIZE],
+            &params,
+            &hash,
+            &proof
+        )
+        .unwrap());
+        assert!(!verify(prev_hash, root_hash, nonce, &test_params(2), &hash, &proof).unwrap());
+
+        // A tampered witness must be rejected.
+        let mut tampered = proof;
+        tampered.witnesses[1] += BigUint::from(1u8);
+        assert!(!verify(prev_hash, root_hash, nonce, &params, &hash, &tampered).unwrap());
+    }
+
+    #[test]
+    fn custom_output_length() {
+        let params = PrimeParams::builder()
+            .penalty(10)
+            .delay(10)
+            .vdf_iterations(1)
+            .output_len(64)
+            .build()
+            .unwrap();
+
+        let output = prime_with_params(
+            [1; INPUT_HASH_SIZE],
+            [2; INPUT_HASH_SIZE],
+            [3; NONCE_SIZE],
+            &params,
+        )
+        .unwrap();
+        assert_eq!(output.len(), 64);
+
+        // The first 32 bytes match the default output length.
+        assert_eq!(
+            output[..32],
+            prime(
+                [1; INPUT_HASH_SIZE],
+                [2; INPUT_HASH_SIZE],
+                [3; NONCE_SIZE],
+                10,
+                10,
+                1
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn custom_finalizers() {
+        let (prev_hash, root_hash, nonce) =
+            ([1; INPUT_HASH_SIZE], [2; INPUT_HASH_SIZE], [3; NONCE_SIZE]);
+        let params = test_params(1);
+
+        let keccak = prime_with(
+            Keccak::with_rate_capacity(params.rate(), params.capacity()),
+            prev_hash,
+            root_hash,
+            nonce,
+            &params,
+        )
+        .unwrap();
+        assert_eq!(
+            keccak,
+            prime_with_params(prev_hash, root_hash, nonce, &params).unwrap()
+        );
+
+        let sha3 = prime_with(Sha3::v256(), prev_hash, root_hash, nonce, &params).unwrap();
+        let shake = prime_with(Shake::v256(), prev_hash, root_hash, nonce, &params).unwrap();
+
+        assert_ne!(keccak, sha3);
+        assert_ne!(sha3, shake);
+    }
+
+    #[cfg(feature = "cshake")]
+    #[test]
+    fn cshake_finalizer() {
+        use crate::CShake;
+
+        let (prev_hash, root_hash, nonce) =
+            ([1; INPUT_HASH_SIZE], [2; INPUT_HASH_SIZE], [3; NONCE_SIZE]);
+        let params = test_params(1);
+
+        let shake = prime_with(CShake::v256(b"", b""), prev_hash, root_hash, nonce, &params);
+        let cshake = prime_with(
+            CShake::v256(b"", b"Zenotta"),
+            prev_hash,
+            root_hash,
+            nonce,
+            &params,
+        );
+
+        // cSHAKE with empty strings is SHAKE; a domain-separation string must change the output.
+        assert_eq!(
+            shake.as_ref().unwrap(),
+            &prime_with(Shake::v256(), prev_hash, root_hash, nonce, &params).unwrap()
+        );
+        assert_ne!(shake.unwrap(), cshake.unwrap());
+    }
+
+    #[test]
+    fn xof_output() {
+        let (prev_hash, root_hash, nonce) =
+            ([1; INPUT_HASH_SIZE], [2; INPUT_HASH_SIZE], [3; NONCE_SIZE]);
+        let params = test_params(1);
+
+        // Derive a 64-byte block ID and extra key material from a single evaluation.
+        let mut xof = prime_xof(prev_hash, root_hash, nonce, &params).unwrap();
+        let mut block_id = [0u8; 64];
+        let mut key_material = [0u8; 32];
+        xof.squeeze(&mut block_id);
+        xof.squeeze(&mut key_material);
+
+        assert_eq!(
+            block_id[..32],
+            prime(prev_hash, root_hash, nonce, 10, 10, 1).unwrap()
+        );
+        assert_ne!(block_id[..32], key_material);
+    }
+
+    #[test]
+    fn wesolowski_chain() {
+        let (prev_hash, root_hash, nonce) = ([1u8; 32], [2u8; 32], [3u8; 8]);
+        let params = PrimeParams::builder()
+            .penalty(10)
+            .delay(200)
+            .vdf_iterations(2)
+            .vdf(VdfKind::Wesolowski)
+            .build()
+            .unwrap();
+
+        let (hash, proof) = prime_with_proof(prev_hash, root_hash, nonce, &params).unwrap();
+        assert_eq!(
+            hash,
+            prime_with_params(prev_hash, root_hash, nonce, &params).unwrap()
+        );
+        assert!(verify(prev_hash, root_hash, nonce, &params, &hash, &proof).unwrap());
+
+        // Selecting a different VDF changes the output.
+        let sloth = PrimeParams::builder()
+            .penalty(10)
+            .delay(200)
+            .vdf_iterations(2)
+            .build()
+            .unwrap();
+        assert_ne!(
+            hash,
+            prime_with_params(prev_hash, root_hash, nonce, &sloth).unwrap()
+        );
+        assert!(!verify(prev_hash, root_hash, nonce, &sloth, &hash, &proof).unwrap());
+
+        let mut tampered = proof.clone();
+        if let VdfProof::Wesolowski(proof) = &mut tampered.vdf_proofs[0] {
+            proof.pi += 1u8;
+        }
+        assert!(!verify(prev_hash, root_hash, nonce, &params, &hash, &tampered).unwrap());
+
+        let mut truncated = proof;
+        truncated.vdf_proofs.pop();
+        assert!(!verify(prev_hash, root_hash, nonce, &params, &hash, &truncated).unwrap());
+    }
+
+    #[test]
+    fn sponge_versions() {
+        let (prev_hash, root_hash, nonce) =
+            ([1; INPUT_HASH_SIZE], [2; INPUT_HASH_SIZE], [3; NONCE_SIZE]);
+        let legacy = test_params(1);
+        let v1 = PrimeParams::builder()
+            .penalty(10)
+            .delay(10)
+            .vdf_iterations(1)
+            .sponge(SpongeVersion::V1)
+            .build()
+            .unwrap();
+
+        // The V1 sponge is the standard Keccak-256 sponge.
+        let hash = prime_with_params(prev_hash, root_hash, nonce, &v1).unwrap();
+        assert_eq!(
+            hash,
+            prime_with(Keccak::v256(), prev_hash, root_hash, nonce, &v1).unwrap()
+        );
+        assert_ne!(
+            hash,
+            prime_with_params(prev_hash, root_hash, nonce, &legacy).unwrap()
+        );
+    }
+
+    // XOR can't tell trailing zero bytes or swapped chunks apart, so it is only used
+    // by the fixed-array functions.
+    #[test]
+    fn xor_rejects_headers() {
+        assert!(matches!(
+            KeccakPrime::new(test_params(1), [3; NONCE_SIZE]),
+            Err(KeccakPrimeError::UnsupportedKdf(ExpansionKdf::Xor))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "cshake")]
+    fn arbitrary_headers() {
+        use crate::Hasher;
+
+        let (prev_hash, root_hash, nonce) =
+            ([1; INPUT_HASH_SIZE], [2; INPUT_HASH_SIZE], [3; NONCE_SIZE]);
+
+        let params = PrimeParams::builder()
+            .penalty(10)
+            .delay(10)
+            .vdf_iterations(1)
+            .kdf(ExpansionKdf::CShake256)
+            .build()
+            .unwrap();
+        let hash = |header: &[&[u8]]| {
+            let mut keccak_prime = KeccakPrime::new(params.clone(), nonce).unwrap();
+            for part in header {
+                keccak_prime.update(part);
+            }
+            let mut output = [0u8; INPUT_HASH_SIZE];
+            keccak_prime.finalize(&mut output);
+            output
+        };
+
+        // The block hashes as a header are equal to the fixed-array functions,
+        // however the header is split into updates.
+        let expected = prime_with_params(prev_hash, root_hash, nonce, &params).unwrap();
+        assert_eq!(hash(&[&prev_hash, &root_hash]), expected[..]);
+        assert_eq!(
+            hash(&[&prev_hash[..5], &prev_hash[5..], &root_hash]),
+            expected[..]
+        );
+
+        let mut keccak_prime = KeccakPrime::new(params.clone(), nonce).unwrap();
+        keccak_prime.update(&prev_hash);
+        keccak_prime.update(&root_hash);
+        let (proof_hash, proof) = keccak_prime.finalize_with_proof().unwrap();
+        assert_eq!(proof_hash, expected);
+        assert!(verify(prev_hash, root_hash, nonce, &params, &proof_hash, &proof).unwrap());
+
+        // Headers of other lengths are supported, the cSHAKE256 key derivation encodes
+        // the length.
+        let header = [7u8; 100];
+        assert_ne!(hash(&[&header]), hash(&[&header[..99]]));
+        assert_ne!(hash(&[&header]), hash(&[&header, &[0]]));
+    }
+
+    fn test_params(vdf_iterations: usize) -> PrimeParams {
+        PrimeParams::builder()
+            .penalty(10)
+            .delay(10)
+            .vdf_iterations(vdf_iterations)
+            .build()
+            .unwrap()
     }
 }
@@ -1,4 +1,4 @@
-use crate::{bits_to_rate, keccakf::KeccakF, Hasher, KeccakState};
+use crate::{bits_to_rate, keccakf::KeccakF, Hasher, KeccakState, PenalizedHasher};
 
 /// The `SHA3` hash functions defined in [`FIPS-202`].
 ///
@@ -76,3 +76,9 @@ impl Hasher for Sha3 {
         self.state.finalize(output);
     }
 }
+
+impl PenalizedHasher for Sha3 {
+    fn finalize_with_penalty_into(self, penalty: usize, output: &mut [u8]) {
+        self.state.finalize_with_penalty_into(penalty, output);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{bits_to_rate, keccakf::KeccakF, Hasher, KeccakState, Xof};
+use crate::{bits_to_rate, keccakf::KeccakF, Hasher, KeccakState, PenalizedHasher, Xof};
 
 /// The `SHAKE` extendable-output functions defined in [`FIPS-202`].
 ///
@@ -49,6 +49,12 @@ impl Hasher for Shake {
     }
 }
 
+impl PenalizedHasher for Shake {
+    fn finalize_with_penalty_into(self, penalty: usize, output: &mut [u8]) {
+        self.state.finalize_with_penalty_into(penalty, output);
+    }
+}
+
 impl Xof for Shake {
     fn squeeze(&mut self, output: &mut [u8]) {
         self.state.squeeze(output)
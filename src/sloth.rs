@@ -1,170 +1,924 @@
 //! Sloth VDF implementation.
 //!
 //! Implementation follows the Section 3 in paper "A random zoo" [1].
-//! We use a single round of Keccak-f[1600] as a permutation function.
+//! By default, we use the prime p = 2^1600 - 2273 and a single round of Keccak-f[1600] as a
+//! permutation function. Other primes and permutations can be selected with [`SlothParams`].
 //!
 //! [1] https://csrc.nist.gov/csrc/media/events/workshop-on-elliptic-curve-cryptography-standards/documents/papers/session1-wesolowski-paper.pdf
 
-// TODO: implement this module using crypto_bigint once it's more stable.
-
-// use crypto_bigint::{nlimbs, MulMod, UInt};
 use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 use lazy_static::lazy_static;
-use num_bigint::{BigInt, BigUint, Sign};
+use num_bigint::BigUint;
 use num_traits::{One, Zero};
 
-use crate::{inverse::inverse_keccak_function, keccakf::RC, WORDS};
-
-// Define a single-round Keccak-f and its inverse - we use it as permutation function.
-inverse_keccak_function!("`inverse-keccak-f[1600, 1]`", inverse_keccakf_1, 1, RC);
-keccak_function!("`keccak-f[1600, 1]`", keccakf_1, 1, RC);
+use crate::fp1600::{biguint_to_limbs, is_reduced, limbs_to_biguint, Fp};
+use crate::{
+    keccak::Keccak,
+    keccakf::RC,
+    permutation::{inverse_keccak_rounds, keccak_rounds},
+    Hasher, KeccakF1600, KeccakP1600, WORDS,
+};
 
 /// Defines internal integer type.
 /// This should be at least `2 ^ (2*k)`, where `k` is the security level.
 type Int = BigUint;
 
-// crypto_bigint alternative:
-// type Int = crypto_bigint::UInt<{ nlimbs!(1600) }>;
-// pub const SEED: Int = Int::from_be_hex("fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff71f");
+/// Width of the permutation state, in bits.
+const PERMUTATION_BITS: u64 = (WORDS * 64) as u64;
+
+/// Size of [`SlothParams::id`], in bytes.
+pub const PARAMS_ID_SIZE: usize = 32;
+
+/// Number of Feistel rounds used to build a permutation for primes narrower than the state.
+const FEISTEL_ROUNDS: u64 = 4;
 
 lazy_static! {
     /// Seed number for Sloth VDF: p = 2^1600 – 2273
     static ref SEED: Int = Int::parse_bytes(b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff71f", 16).unwrap();
-    static ref SEED_SIGNED: BigInt = BigInt::from_biguint(Sign::Plus, SEED.clone());
-    static ref SEED_EXPONENT: Int = (SEED.clone() - 3u32) / 4u32;
+    /// Seed number for cheaper Sloth instances: p = 2^256 - 189
+    static ref SEED_256: Int = (Int::one() << 256u32) - 189u32;
+    static ref DEFAULT_PARAMS: SlothParams = SlothParams::p1600();
+}
+
+/// Permutation used as the Sigma function.
+///
+/// It can also be selected in a type-safe way from [`KeccakF1600`] and [`KeccakP1600`]:
+/// `SlothPermutation::from(KeccakP1600::<12>)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlothPermutation {
+    /// Keccak-f[1600] reduced to the given number of rounds (from 1 to 24),
+    /// using the round constants of the first rounds. Same as [`KeccakF1600`].
+    KeccakF(usize),
+    /// Keccak-p[1600] with the given number of rounds (from 1 to 24), i.e. the last rounds
+    /// of Keccak-f[1600]. Same as [`KeccakP1600`]; 12 rounds are used in KangarooTwelve.
+    KeccakP(usize),
+}
+
+impl SlothPermutation {
+    fn rounds(&self) -> usize {
+        match *self {
+            SlothPermutation::KeccakF(rounds) | SlothPermutation::KeccakP(rounds) => rounds,
+        }
+    }
+
+    fn round_constants(&self) -> &'static [u64] {
+        match *self {
+            SlothPermutation::KeccakF(rounds) => &RC[..rounds],
+            SlothPermutation::KeccakP(rounds) => &RC[RC.len() - rounds..],
+        }
+    }
+
+    fn permute(&self, a: &mut [u64; WORDS]) {
+        keccak_rounds(a, self.round_constants());
+    }
+
+    fn inverse(&self, a: &mut [u64; WORDS]) {
+        inverse_keccak_rounds(a, self.round_constants());
+    }
+}
+
+impl<const R: usize> From<KeccakF1600<R>> for SlothPermutation {
+    fn from(_: KeccakF1600<R>) -> Self {
+        SlothPermutation::KeccakF(R)
+    }
+}
+
+impl<const R: usize> From<KeccakP1600<R>> for SlothPermutation {
+    fn from(_: KeccakP1600<R>) -> Self {
+        SlothPermutation::KeccakP(R)
+    }
+}
+
+/// Parameters of a Sloth VDF instance: the prime modulus, the Sigma permutation and
+/// the evaluation mode.
+///
+/// If the prime is narrower than the 1600-bit permutation state, Sigma is a Feistel network
+/// over the bit width of the prime, with the permutation as its round function. Cycle-walking
+/// keeps the result below the prime, so Sigma stays a permutation of the field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlothParams {
+    modulus: Int,
+    /// (p - 3) / 4
+    exponent: Int,
+    permutation: SlothPermutation,
+    mode: SlothMode,
+}
+
+impl SlothParams {
+    /// Creates a new set of Sloth parameters.
+    ///
+    /// The `modulus` is expected to be a prime. It must be congruent to 3 mod 4 so that
+    /// square roots can be computed with a single exponentiation, and it must fit into the
+    /// permutation state. [`SlothMode::ConstantTime`] is only supported for the default prime.
+    pub fn new(
+        modulus: Int,
+        permutation: SlothPermutation,
+        mode: SlothMode,
+    ) -> Result<SlothParams, SlothParamsError> {
+        if modulus.bits() > PERMUTATION_BITS {
+            return Err(SlothParamsError::ModulusTooLarge(modulus.bits()));
+        }
+        if modulus <= Int::from(3u8) {
+            return Err(SlothParamsError::ModulusTooSmall);
+        }
+        if &modulus % 4u8 != Int::from(3u8) {
+            return Err(SlothParamsError::ModulusNotThreeModFour);
+        }
+        if !(1..=RC.len()).contains(&permutation.rounds()) {
+            return Err(SlothParamsError::InvalidRounds(permutation.rounds()));
+        }
+        if mode == SlothMode::ConstantTime && modulus != *SEED {
+            return Err(SlothParamsError::ConstantTimeUnsupported);
+        }
+
+        Ok(SlothParams {
+            exponent: (&modulus - 3u8) / 4u8,
+            modulus,
+            permutation,
+            mode,
+        })
+    }
+
+    /// Default parameters: p = 2^1600 - 2273 with a single round of Keccak-f[1600].
+    pub fn p1600() -> SlothParams {
+        SlothParams::new(
+            SEED.clone(),
+            SlothPermutation::KeccakF(1),
+            SlothMode::VariableTime,
+        )
+        .expect("valid parameters")
+    }
+
+    /// Cheaper parameters for test networks: p = 2^256 - 189 with a single round of
+    /// Keccak-f[1600].
+    pub fn p256() -> SlothParams {
+        SlothParams::new(
+            SEED_256.clone(),
+            SlothPermutation::KeccakF(1),
+            SlothMode::VariableTime,
+        )
+        .expect("valid parameters")
+    }
+
+    /// Prime modulus.
+    pub fn modulus(&self) -> &Int {
+        &self.modulus
+    }
+
+    /// Permutation used as the Sigma function.
+    pub fn permutation(&self) -> SlothPermutation {
+        self.permutation
+    }
+
+    /// Evaluation mode.
+    pub fn mode(&self) -> SlothMode {
+        self.mode
+    }
+
+    /// Same as [`solve`], but with these parameters.
+    pub fn solve(&self, s: Int, delay: u64) -> Result<Int, SlothError> {
+        self.check_input(&s)?;
+
+        Ok(match self.fixed_backend() {
+            Some(backend) => solve_in(&backend, s, delay),
+            None => solve_in(self, s, delay),
+        })
+    }
+
+    /// Same as [`solve_with`], but with these parameters.
+    pub fn solve_with<P: ProgressSink>(
+        &self,
+        s: Int,
+        delay: u64,
+        interval: u64,
+        sink: &mut P,
+    ) -> Result<Int, SlothError> {
+        self.check_input(&s)?;
+
+        let witness = match self.fixed_backend() {
+            Some(backend) => solve_with_in(&backend, s, delay, interval, sink),
+            None => solve_with_in(self, s, delay, interval, sink),
+        }?;
+        Ok(witness)
+    }
+
+    /// Same as [`verify`], but with these parameters.
+    pub fn verify(&self, s: Int, w: Int, delay: u64) -> Result<bool, SlothError> {
+        self.check_input(&s)?;
+        if w >= self.modulus {
+            return Err(SlothError::WitnessOutOfRange);
+        }
+
+        Ok(match self.fixed_backend() {
+            Some(backend) => verify_in(&backend, s, w, delay),
+            None => verify_in(self, s, w, delay),
+        })
+    }
+
+    /// Same as [`verify_batch`], but with these parameters and `threads` worker threads.
+    pub fn verify_batch(
+        &self,
+        items: &[(Int, Int, u64)],
+        threads: usize,
+    ) -> Result<(), BatchFailure> {
+        let threads = threads.clamp(1, items.len().max(1));
+
+        // Index of the next item to verify, shared by all workers.
+        let next = AtomicUsize::new(0);
+        // Lowest failing index found so far, `usize::MAX` if there's none yet.
+        let first_failure = AtomicUsize::new(usize::MAX);
+
+        let failures: Vec<BatchFailure> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    let (next, first_failure) = (&next, &first_failure);
+
+                    scope.spawn(move || {
+                        let mut failure = None;
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            if index >= items.len() || index > first_failure.load(Ordering::SeqCst)
+                            {
+                                break;
+                            }
+
+                            let (s, w, delay) = &items[index];
+                            let error = match self.verify(s.clone(), w.clone(), *delay) {
+                                Ok(true) => continue,
+                                Ok(false) => None,
+                                Err(e) => Some(e),
+                            };
+                            // Indices are taken in ascending order, so this is the lowest
+                            // failure of this worker.
+                            first_failure.fetch_min(index, Ordering::SeqCst);
+                            failure = Some(BatchFailure { index, error });
+                            break;
+                        }
+                        failure
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .filter_map(|worker| worker.join().expect("verification worker panicked"))
+                .collect()
+        });
+
+        match failures.into_iter().min_by_key(|failure| failure.index) {
+            Some(failure) => Err(failure),
+            None => Ok(()),
+        }
+    }
+
+    /// Solves the puzzle for `input` and packs the result into a [`SlothProof`].
+    /// Like [`solve`](SlothParams::solve), fails if the input is not below p.
+    pub fn prove(&self, input: Int, delay: u64) -> Result<SlothProof, SlothError> {
+        Ok(SlothProof {
+            witness: self.solve(input.clone(), delay)?,
+            input,
+            delay,
+            params_id: self.id(),
+        })
+    }
+
+    /// Identifier of the parameters: Keccak-256 of the modulus and the permutation.
+    ///
+    /// The evaluation mode doesn't affect outputs, so it isn't included.
+    pub fn id(&self) -> [u8; PARAMS_ID_SIZE] {
+        let (tag, rounds) = match self.permutation {
+            SlothPermutation::KeccakF(rounds) => (0u8, rounds),
+            SlothPermutation::KeccakP(rounds) => (1u8, rounds),
+        };
+
+        let mut keccak = Keccak::v256();
+        keccak.update(&self.modulus.to_bytes_be());
+        keccak.update(&[tag]);
+        keccak.update(&(rounds as u64).to_be_bytes());
+
+        let mut id = [0u8; PARAMS_ID_SIZE];
+        keccak.finalize(&mut id);
+        id
+    }
+
+    fn check_input(&self, s: &Int) -> Result<(), SlothError> {
+        if *s >= self.modulus {
+            return Err(SlothError::InputOutOfRange);
+        }
+        Ok(())
+    }
+
+    /// Size of an integer in the canonical encoding, in bytes.
+    fn element_size(&self) -> usize {
+        self.modulus.bits().div_ceil(8) as usize
+    }
+
+    /// Returns the fixed-width backend if it is selected for these parameters.
+    fn fixed_backend(&self) -> Option<Fp1600Backend> {
+        let fixed = match self.mode {
+            SlothMode::ConstantTime => true,
+            SlothMode::VariableTime => cfg!(feature = "sloth_fp1600") && self.modulus == *SEED,
+        };
+
+        if fixed {
+            Some(Fp1600Backend {
+                permutation: self.permutation,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Implements the Rho function as seen in Section 3.2 of the paper.
+    fn rho(&self, x: Int) -> Int {
+        let x1 = x.modpow(&self.exponent, &self.modulus);
+
+        let x2 = (&x * &x1) % &self.modulus; // = x ^ ((p + 1) / 4)
+        let is_even = &x2 % Int::from(2u8) == Int::zero();
+
+        let x3 = (&x1 * &x2) % &self.modulus; // = x ^ ((p - 1) / 2)
+
+        // Check for quadratic residue
+        let quad_res = x3 <= Int::one();
+
+        if is_even == quad_res {
+            x2
+        } else {
+            (&self.modulus - &x2) % &self.modulus
+        }
+    }
+
+    /// Inverse Rho function.
+    fn rho_inverse(&self, x: Int) -> Int {
+        let is_even = &x % 2u8 == Int::zero();
+        let square = &x * &x % &self.modulus;
+
+        if is_even {
+            square
+        } else {
+            (&self.modulus - square) % &self.modulus
+        }
+    }
+
+    /// Permutation function. Maps `[0, p)` onto itself.
+    fn sigma(&self, x: Int) -> Int {
+        // Cycle-walking terminates because the cycle of the permutation containing `x`
+        // has at least one element below the modulus: `x` itself.
+        let mut y = self.permute(x);
+        while y >= self.modulus {
+            y = self.permute(y);
+        }
+        y
+    }
+
+    /// Inverse of a permutation function.
+    fn sigma_inverse(&self, x: Int) -> Int {
+        let mut y = self.permute_inverse(x);
+        while y >= self.modulus {
+            y = self.permute_inverse(y);
+        }
+        y
+    }
+
+    /// Permutation over the bit width of the modulus.
+    fn permute(&self, x: Int) -> Int {
+        if self.modulus.bits() == PERMUTATION_BITS {
+            let mut state = int_to_state(&x);
+            self.permutation.permute(&mut state);
+            limbs_to_biguint(&state)
+        } else {
+            self.feistel(x)
+        }
+    }
+
+    fn permute_inverse(&self, x: Int) -> Int {
+        if self.modulus.bits() == PERMUTATION_BITS {
+            let mut state = int_to_state(&x);
+            self.permutation.inverse(&mut state);
+            limbs_to_biguint(&state)
+        } else {
+            self.feistel_inverse(x)
+        }
+    }
+
+    /// Number of bits in each half of the Feistel network.
+    fn feistel_half_bits(&self) -> u64 {
+        self.modulus.bits().div_ceil(2)
+    }
+
+    /// Feistel round function: the permutation applied to a half of the input, with
+    /// the round number in the last lane of the state for domain separation.
+    fn feistel_round(&self, round: u64, half: &Int) -> Int {
+        let mut state = int_to_state(half);
+        state[WORDS - 1] ^= round + 1;
+        self.permutation.permute(&mut state);
+        limbs_to_biguint(&state) & ((Int::one() << self.feistel_half_bits()) - 1u8)
+    }
+
+    fn feistel(&self, x: Int) -> Int {
+        let half_bits = self.feistel_half_bits();
+        let mut left = &x >> half_bits;
+        let mut right = x & ((Int::one() << half_bits) - 1u8);
+
+        for round in 0..FEISTEL_ROUNDS {
+            let f = self.feistel_round(round, &right);
+            let new_right = left ^ f;
+            left = right;
+            right = new_right;
+        }
+
+        (left << half_bits) | right
+    }
+
+    fn feistel_inverse(&self, x: Int) -> Int {
+        let half_bits = self.feistel_half_bits();
+        let mut left = &x >> half_bits;
+        let mut right = x & ((Int::one() << half_bits) - 1u8);
+
+        for round in (0..FEISTEL_ROUNDS).rev() {
+            let f = self.feistel_round(round, &left);
+            let new_left = right ^ f;
+            right = left;
+            left = new_left;
+        }
+
+        (left << half_bits) | right
+    }
+}
+
+impl Default for SlothParams {
+    fn default() -> Self {
+        SlothParams::p1600()
+    }
+}
+
+/// Sloth puzzle solution which can be shipped between nodes.
+///
+/// Integers are encoded big-endian with a fixed width of the modulus size (200 bytes
+/// for p = 2^1600 - 2273), so the encoding of a proof is unique:
+/// `input || witness || delay (u64, big-endian) || params_id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlothProof {
+    /// Puzzle input.
+    pub input: Int,
+    /// Witness obtained by solving the puzzle.
+    pub witness: Int,
+    /// Puzzle difficulty.
+    pub delay: u64,
+    /// Identifier of the parameters the puzzle has been solved with, see [`SlothParams::id`].
+    pub params_id: [u8; PARAMS_ID_SIZE],
 }
 
-/// Implements the Rho function as seen in Section 3.2 of the paper.
-fn rho(x: Int) -> Int {
-    let x1 = x.modpow(&*SEED_EXPONENT, &*SEED);
+impl SlothProof {
+    /// Checks the proof against `params`.
+    ///
+    /// ## Returns
+    /// - `true` if the proof has been created with `params` and the witness is valid.
+    pub fn verify(&self, params: &SlothParams) -> bool {
+        self.params_id == params.id()
+            && params
+                .verify(self.input.clone(), self.witness.clone(), self.delay)
+                .unwrap_or(false)
+    }
+
+    /// Encodes the proof for `params`.
+    pub fn to_bytes(&self, params: &SlothParams) -> Result<Vec<u8>, SlothProofError> {
+        if self.params_id != params.id() {
+            return Err(SlothProofError::ParamsMismatch);
+        }
+
+        let size = params.element_size();
+        let mut bytes = Vec::with_capacity(2 * size + 8 + PARAMS_ID_SIZE);
+        for value in [&self.input, &self.witness] {
+            if value >= params.modulus() {
+                return Err(SlothProofError::NonCanonical);
+            }
+            let value = value.to_bytes_be();
+            bytes.resize(bytes.len() + size - value.len(), 0);
+            bytes.extend_from_slice(&value);
+        }
+        bytes.extend_from_slice(&self.delay.to_be_bytes());
+        bytes.extend_from_slice(&self.params_id);
+        Ok(bytes)
+    }
 
-    let x2 = (&x * &x1) % &*SEED; // = x ^ ((p + 1) / 4)
-    let is_even = &x2 % Int::from(2u8) == Int::zero();
+    /// Decodes a proof created with [`to_bytes`] for the same `params`.
+    ///
+    /// Decoding is strict: integers which are not reduced modulo p are rejected.
+    ///
+    /// [`to_bytes`]: SlothProof::to_bytes
+    pub fn from_bytes(bytes: &[u8], params: &SlothParams) -> Result<SlothProof, SlothProofError> {
+        let size = params.element_size();
+        if bytes.len() != 2 * size + 8 + PARAMS_ID_SIZE {
+            return Err(SlothProofError::InvalidLength(bytes.len()));
+        }
 
-    let x3 = (&x1 * &x2) % &*SEED; // = x ^ ((p - 1) / 2)
+        let (input, rest) = bytes.split_at(size);
+        let (witness, rest) = rest.split_at(size);
+        let (delay, params_id) = rest.split_at(8);
 
-    // Check for quadratic residue
-    let quad_res = x3 <= Int::one();
+        let input = Int::from_bytes_be(input);
+        let witness = Int::from_bytes_be(witness);
+        if input >= *params.modulus() || witness >= *params.modulus() {
+            return Err(SlothProofError::NonCanonical);
+        }
+        if params_id != params.id() {
+            return Err(SlothProofError::ParamsMismatch);
+        }
 
-    if is_even == quad_res {
-        x2
-    } else {
-        (&*SEED - &x2) % &*SEED
+        Ok(SlothProof {
+            input,
+            witness,
+            delay: u64::from_be_bytes(delay.try_into().unwrap()),
+            params_id: params_id.try_into().unwrap(),
+        })
     }
 }
 
-/// Inverse Rho function.
-fn rho_inverse(x: Int) -> Int {
-    let x = BigInt::from(x);
-    let is_even = &x % 2 == BigInt::zero();
+/// Errors in [`SlothProof`] encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlothProofError {
+    /// The encoded proof has an unexpected length; contains the actual length.
+    InvalidLength(usize),
+    /// An integer is not reduced modulo p.
+    NonCanonical,
+    /// The proof has been created with different parameters.
+    ParamsMismatch,
+}
 
-    let multiplier = BigInt::from(if is_even { 1 } else { -1 });
+impl fmt::Display for SlothProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlothProofError::InvalidLength(len) => {
+                write!(f, "unexpected length of an encoded proof: {}", len)
+            }
+            SlothProofError::NonCanonical => write!(f, "integer is not reduced modulo p"),
+            SlothProofError::ParamsMismatch => {
+                write!(f, "proof has been created with different parameters")
+            }
+        }
+    }
+}
 
-    ((&multiplier * &x * &x % &*SEED_SIGNED + &*SEED_SIGNED) % &*SEED_SIGNED)
-        .to_biguint()
-        // unwrap is fine here because `mod SEED_SIGNED` guarantees it's a positive number
-        .unwrap()
+impl Error for SlothProofError {}
+
+/// Invalid Sloth parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlothParamsError {
+    /// The modulus doesn't fit into the permutation state; contains its size in bits.
+    ModulusTooLarge(u64),
+    /// The modulus must be larger than 3.
+    ModulusTooSmall,
+    /// The modulus isn't congruent to 3 mod 4.
+    ModulusNotThreeModFour,
+    /// The number of Keccak-f rounds must be between 1 and 24.
+    InvalidRounds(usize),
+    /// The constant-time mode is only available for p = 2^1600 - 2273.
+    ConstantTimeUnsupported,
+}
+
+impl fmt::Display for SlothParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlothParamsError::ModulusTooLarge(bits) => write!(
+                f,
+                "modulus of {} bits doesn't fit into {} bits",
+                bits, PERMUTATION_BITS
+            ),
+            SlothParamsError::ModulusTooSmall => write!(f, "modulus must be larger than 3"),
+            SlothParamsError::ModulusNotThreeModFour => {
+                write!(f, "modulus must be congruent to 3 mod 4")
+            }
+            SlothParamsError::InvalidRounds(rounds) => write!(
+                f,
+                "number of rounds must be between 1 and {}, got {}",
+                RC.len(),
+                rounds
+            ),
+            SlothParamsError::ConstantTimeUnsupported => {
+                write!(
+                    f,
+                    "constant-time mode is only supported for p = 2^1600 - 2273"
+                )
+            }
+        }
+    }
 }
 
-/// Permutation function. It is implemented using a single round of Keccak.
-fn sigma(x: Int) -> Int {
-    let mut bytes = (x % &*SEED).to_u64_digits();
+impl Error for SlothParamsError {}
 
-    // Ensure the `bytes` length is exactly `keccak_prime::WORDS` bytes.
-    bytes.resize(WORDS, 0);
+/// Converts an integer into a Keccak state.
+fn int_to_state(x: &Int) -> [u64; WORDS] {
+    biguint_to_limbs(x).expect("unexpected incorrect input for keccak-f")
+}
+
+/// Arithmetic backend used to evaluate Sloth chains.
+trait Backend {
+    /// Element of the chain.
+    type Elem;
+
+    fn to_elem(&self, x: Int) -> Self::Elem;
+    fn to_int(&self, x: Self::Elem) -> Int;
+    fn tau(&self, x: Self::Elem) -> Self::Elem;
+    fn tau_inverse(&self, x: Self::Elem) -> Self::Elem;
+}
 
-    // Convert Int into a bit string suitable for Keccak-f which is `[u64; WORDS]`.
-    let mut byte_array = bytes
-        .try_into()
-        .expect("unexpected incorrect input for keccak-f");
+/// Arbitrary-precision backend based on `num_bigint`, which supports any parameters.
+impl Backend for SlothParams {
+    type Elem = Int;
 
-    // Apply a single round of Keccak-f.
-    keccakf_1(&mut byte_array);
+    fn to_elem(&self, x: Int) -> Int {
+        x
+    }
+
+    fn to_int(&self, x: Int) -> Int {
+        x
+    }
+
+    /// Implements the Tau function as seen in Section 3.2 of the paper.
+    /// It composes the Rho function with the permutation function Sigma.
+    fn tau(&self, x: Int) -> Int {
+        self.sigma(self.rho(x))
+    }
+
+    /// Implements the inverse Tau function.
+    fn tau_inverse(&self, x: Int) -> Int {
+        self.rho_inverse(self.sigma_inverse(x))
+    }
+}
 
-    // Convert a bit string into an integer.
-    // num_bigint expects the input to be `Vec<u32>`.
-    // FIXME: get rid of unsafety?
-    Int::new(unsafe {
-        let (_prefix, digits_u32, _suffix) = byte_array.align_to::<u32>();
-        digits_u32.to_owned()
-    })
+/// Fixed-width, constant-time backend specialised to p = 2^1600 - 2273.
+///
+/// Sigma cycle-walks just like in the `BigUint` backend. A permutation output is at least p
+/// with a negligible probability of about 2^-1589, so the branch doesn't leak the witness.
+struct Fp1600Backend {
+    permutation: SlothPermutation,
 }
 
-/// Inverse of a permutation function. In our case, it's simply a
-/// single round of inverse Keccak-f[1600].
-fn sigma_inverse(x: Int) -> Int {
-    let mut bytes = x.to_u64_digits();
+impl Backend for Fp1600Backend {
+    type Elem = Fp;
+
+    fn to_elem(&self, x: Int) -> Fp {
+        Fp::reduce(&biguint_to_limbs(&x).expect("input is below the modulus"))
+    }
+
+    fn to_int(&self, x: Fp) -> Int {
+        limbs_to_biguint(x.limbs())
+    }
+
+    fn tau(&self, x: Fp) -> Fp {
+        let mut w = *rho_ct(x).limbs();
+        self.permutation.permute(&mut w);
+        while !is_reduced(&w) {
+            self.permutation.permute(&mut w);
+        }
+        Fp::reduce(&w)
+    }
 
-    // Ensure the `bytes` length is exactly `keccak_prime::WORDS` bytes.
-    bytes.resize(WORDS, 0);
+    fn tau_inverse(&self, x: Fp) -> Fp {
+        let mut w = *x.limbs();
+        self.permutation.inverse(&mut w);
+        while !is_reduced(&w) {
+            self.permutation.inverse(&mut w);
+        }
+        rho_inverse_ct(Fp::reduce(&w))
+    }
+}
 
-    // Convert Int into a bit string suitable for Keccak-f which is `[u64; WORDS]`.
-    let mut byte_array = bytes
-        .try_into()
-        .expect("unexpected incorrect input for keccak-f");
+/// Constant-time Rho function over the fixed-width field.
+///
+/// The exponent is public, and the sign of the square root is chosen with a branch-free
+/// conditional negation, so the timing doesn't depend on the witness.
+fn rho_ct(x: Fp) -> Fp {
+    let x1 = x.pow_sqrt_exponent();
+    let x2 = x.mul(&x1); // = x ^ ((p + 1) / 4)
+    let x3 = x1.mul(&x2); // = x ^ ((p - 1) / 2)
 
-    // Apply inverse Keccak-f.
-    inverse_keccakf_1(&mut byte_array);
+    let is_even = x2.is_odd() ^ 1;
+    let quad_res = x3.ct_eq(&Fp::ZERO) | x3.ct_eq(&Fp::ONE);
 
-    // Convert a bit string into an integer.
-    // num_bigint expects the input to be `Vec<u32>`.
-    // FIXME: get rid of unsafety?
-    Int::new(unsafe {
-        let (_prefix, digits_u32, _suffix) = byte_array.align_to::<u32>();
-        digits_u32.to_owned()
-    })
+    // Negate unless `is_even == quad_res`.
+    x2.conditional_neg(is_even ^ quad_res)
 }
 
-/// Implements the Tau function as seen in Section 3.2 of the paper.
-/// It composes the Rho function with the permutation function Sigma -
-/// which in our case is a single round of AES-128.
-fn tau(x: Int) -> Int {
-    sigma(rho(x))
+/// Constant-time inverse Rho function over the fixed-width field.
+fn rho_inverse_ct(x: Fp) -> Fp {
+    x.square().conditional_neg(x.is_odd())
 }
 
-/// Implements the inverse Tau function.
-fn tau_inverse(x: Int) -> Int {
-    rho_inverse(sigma_inverse(x))
+/// Selects how Sloth chains are evaluated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SlothMode {
+    /// The fastest available backend. Its timing may depend on the witness.
+    #[default]
+    VariableTime,
+    /// Branch-free fixed-width arithmetic, for when Sloth is used to derive secrets.
+    /// Only the conversions of the input and output integers are variable-time.
+    ConstantTime,
 }
 
 /// ## Arguments
-/// - `s` is the security parameter.
+/// - `s` is the security parameter, which must be below p.
 /// - `delay` is the desired puzzle difficulty.
 ///
 /// ## Returns
-/// - Witness number.
-pub fn solve(s: Int, delay: u64) -> Int {
-    let mut w_iter = s;
+/// - Witness number, or [`SlothError::InputOutOfRange`] if `s` is not below p.
+pub fn solve(s: Int, delay: u64) -> Result<Int, SlothError> {
+    DEFAULT_PARAMS.solve(s, delay)
+}
+
+/// Same as [`solve`], but evaluated in the selected `mode`.
+pub fn solve_with_mode(s: Int, delay: u64, mode: SlothMode) -> Result<Int, SlothError> {
+    SlothParams {
+        mode,
+        ..DEFAULT_PARAMS.clone()
+    }
+    .solve(s, delay)
+}
+
+fn solve_in<B: Backend>(backend: &B, s: Int, delay: u64) -> Int {
+    let mut w_iter = backend.to_elem(s);
 
     for _ in 0..delay {
-        w_iter = tau(w_iter);
+        w_iter = backend.tau(w_iter);
     }
 
-    w_iter
+    backend.to_int(w_iter)
+}
+
+/// Receives progress updates from [`solve_with`].
+///
+/// It is implemented for closures of the form `FnMut(u64, u64) -> Result<(), Cancelled>`.
+pub trait ProgressSink {
+    /// Called with the number of `iterations` done so far out of `delay`.
+    /// Returning [`Cancelled`] aborts the computation.
+    fn progress(&mut self, iterations: u64, delay: u64) -> Result<(), Cancelled>;
+}
+
+impl<F: FnMut(u64, u64) -> Result<(), Cancelled>> ProgressSink for F {
+    fn progress(&mut self, iterations: u64, delay: u64) -> Result<(), Cancelled> {
+        self(iterations, delay)
+    }
 }
 
+/// Same as [`solve`], but reports progress to `sink` every `interval` iterations.
+///
 /// ## Arguments
 /// - `s` is the security parameter.
-/// - `w` is the witness number obtained from `solve`.
+/// - `delay` is the desired puzzle difficulty.
+/// - `interval` is the number of iterations between progress updates.
+/// - `sink` receives progress updates and can cancel the computation.
+///
+/// ## Returns
+/// - Witness number, or [`SlothError::Cancelled`] if the sink has aborted the computation.
+pub fn solve_with<P: ProgressSink>(
+    s: Int,
+    delay: u64,
+    interval: u64,
+    sink: &mut P,
+) -> Result<Int, SlothError> {
+    DEFAULT_PARAMS.solve_with(s, delay, interval, sink)
+}
+
+fn solve_with_in<B: Backend, P: ProgressSink>(
+    backend: &B,
+    s: Int,
+    delay: u64,
+    interval: u64,
+    sink: &mut P,
+) -> Result<Int, Cancelled> {
+    let interval = interval.max(1);
+    let mut w_iter = backend.to_elem(s);
+
+    for i in 1..=delay {
+        w_iter = backend.tau(w_iter);
+
+        if i % interval == 0 {
+            sink.progress(i, delay)?;
+        }
+    }
+
+    Ok(backend.to_int(w_iter))
+}
+
+/// The computation has been cancelled by a [`ProgressSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Sloth computation has been cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Errors in Sloth computations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlothError {
+    /// The puzzle input is not below the modulus.
+    InputOutOfRange,
+    /// The witness is not below the modulus.
+    WitnessOutOfRange,
+    /// The computation has been cancelled by a [`ProgressSink`].
+    Cancelled,
+}
+
+impl From<Cancelled> for SlothError {
+    fn from(_: Cancelled) -> Self {
+        SlothError::Cancelled
+    }
+}
+
+impl fmt::Display for SlothError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlothError::InputOutOfRange => write!(f, "Sloth input is not below the modulus"),
+            SlothError::WitnessOutOfRange => write!(f, "Sloth witness is not below the modulus"),
+            SlothError::Cancelled => Cancelled.fmt(f),
+        }
+    }
+}
+
+impl Error for SlothError {}
+
+/// ## Arguments
+/// - `s` is the security parameter, which must be below p.
+/// - `w` is the witness number obtained from `solve`, which must be below p.
 /// - `delay` is the puzzle difficulty.
 ///
 /// ## Returns
-/// - `true` if the verification has passed.
-pub fn verify(s: Int, w: Int, delay: u64) -> bool {
-    let mut w_iter = w;
+/// - `true` if the verification has passed, or an error if `s` or `w` is not below p.
+pub fn verify(s: Int, w: Int, delay: u64) -> Result<bool, SlothError> {
+    DEFAULT_PARAMS.verify(s, w, delay)
+}
+
+/// Same as [`verify`], but evaluated in the selected `mode`.
+pub fn verify_with_mode(s: Int, w: Int, delay: u64, mode: SlothMode) -> Result<bool, SlothError> {
+    SlothParams {
+        mode,
+        ..DEFAULT_PARAMS.clone()
+    }
+    .verify(s, w, delay)
+}
+
+/// Verifies many Sloth witnesses, spreading the work over all available CPU cores.
+///
+/// ## Arguments
+/// - `items` are `(s, w, delay)` triples, as passed to [`verify`].
+///
+/// ## Returns
+/// - `Ok(())` if all witnesses are valid, or the failure with the lowest index otherwise.
+pub fn verify_batch(items: &[(Int, Int, u64)]) -> Result<(), BatchFailure> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    DEFAULT_PARAMS.verify_batch(items, threads)
+}
+
+/// First failing item of a [`verify_batch`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchFailure {
+    /// Index of the item in the batch.
+    pub index: usize,
+    /// Error returned by [`verify`], or `None` if the witness is simply wrong.
+    pub error: Option<SlothError>,
+}
+
+impl fmt::Display for BatchFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            Some(e) => write!(f, "Sloth batch item {} is invalid: {}", self.index, e),
+            None => write!(f, "Sloth batch item {} has a wrong witness", self.index),
+        }
+    }
+}
+
+impl Error for BatchFailure {}
+
+fn verify_in<B: Backend>(backend: &B, s: Int, w: Int, delay: u64) -> bool {
+    let mut w_iter = backend.to_elem(w);
 
     for _ in 0..delay {
-        w_iter = tau_inverse(w_iter);
+        w_iter = backend.tau_inverse(w_iter);
     }
 
-    w_iter == s
+    backend.to_int(w_iter) == s
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{solve, verify};
+    use super::{
+        solve, solve_in, solve_with, solve_with_mode, verify, verify_batch, verify_in,
+        verify_with_mode, BatchFailure, Cancelled, Fp1600Backend, SlothError, SlothMode,
+        SlothParams, SlothParamsError, SlothPermutation, SlothProof, SlothProofError, SEED,
+    };
+    use crate::{keccakf, InversePermutation, KeccakF1600, KeccakP1600};
     use num_bigint::BigUint;
+    use num_traits::One;
     use std::time::Instant;
 
     #[test]
@@ -174,12 +928,352 @@ mod tests {
 
         // compute the sloth vdf
         let instant = Instant::now();
-        let witness = solve(x.clone(), t);
+        let witness = solve(x.clone(), t).unwrap();
         println!("{}, eval: {} ms", witness, instant.elapsed().as_millis());
 
         // verify the result
         let instant = Instant::now();
-        assert!(verify(x, witness.clone(), t));
+        assert!(verify(x, witness.clone(), t).unwrap());
         println!("verified in {} ms", instant.elapsed().as_millis());
     }
+
+    #[test]
+    fn progress_and_cancellation() {
+        let x = BigUint::from(11u64);
+
+        let mut updates = Vec::new();
+        let witness = solve_with(x.clone(), 10, 3, &mut |i, delay| {
+            updates.push((i, delay));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(updates, [(3, 10), (6, 10), (9, 10)]);
+        assert_eq!(witness, solve(x.clone(), 10).unwrap());
+
+        // Cancel after the second update.
+        let mut updates = 0;
+        let result = solve_with(x, 10, 3, &mut |_, _| {
+            updates += 1;
+            if updates == 2 {
+                Err(Cancelled)
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(result, Err(SlothError::Cancelled));
+        assert_eq!(updates, 2);
+    }
+
+    // The fixed-width backend must produce exactly the same chains as `BigUint`.
+    #[test]
+    fn differential_backends() {
+        let inputs = [
+            BigUint::from(0u8),
+            BigUint::from(11u64),
+            SEED.clone() - 1u8,
+            BigUint::one() << 1599,
+            BigUint::from_bytes_be(&[0xa5; 136]),
+            BigUint::from_bytes_be(&[0x3c; 199]),
+        ];
+
+        for permutation in [SlothPermutation::KeccakF(1), SlothPermutation::KeccakP(12)] {
+            let params =
+                SlothParams::new(SEED.clone(), permutation, SlothMode::VariableTime).unwrap();
+            let fixed = Fp1600Backend { permutation };
+
+            for x in inputs.iter().cloned() {
+                let witness = solve_in(&params, x.clone(), 3);
+                assert_eq!(witness, solve_in(&fixed, x.clone(), 3));
+
+                assert!(verify_in(&params, x.clone(), witness.clone(), 3));
+                assert!(verify_in(&fixed, x.clone(), witness.clone(), 3));
+                assert!(!verify_in(&fixed, x, witness + 1u8, 3));
+            }
+        }
+    }
+
+    #[test]
+    fn modes_agree() {
+        for x in [BigUint::from(11u64), BigUint::from_bytes_be(&[0xa5; 136])] {
+            let witness = solve_with_mode(x.clone(), 5, SlothMode::VariableTime).unwrap();
+            assert_eq!(
+                witness,
+                solve_with_mode(x.clone(), 5, SlothMode::ConstantTime).unwrap()
+            );
+
+            for mode in [SlothMode::VariableTime, SlothMode::ConstantTime] {
+                assert_eq!(
+                    verify_with_mode(x.clone(), witness.clone(), 5, mode),
+                    Ok(true)
+                );
+                assert_eq!(
+                    verify_with_mode(x.clone(), witness.clone(), 4, mode),
+                    Ok(false)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn permutations() {
+        let mut state = [0u64; 25];
+        state[0] = 11;
+        let mut expected = state;
+
+        SlothPermutation::KeccakF(24).permute(&mut state);
+        keccakf(&mut expected);
+        assert_eq!(state, expected);
+
+        for permutation in [
+            SlothPermutation::KeccakF(1),
+            SlothPermutation::KeccakF(3),
+            SlothPermutation::KeccakP(12),
+        ] {
+            let mut state = expected;
+            permutation.permute(&mut state);
+            assert_ne!(state, expected);
+            permutation.inverse(&mut state);
+            assert_eq!(state, expected);
+        }
+
+        // Type-safe selection matches the generic permutations.
+        let permutation = SlothPermutation::from(KeccakP1600::<12>);
+        assert_eq!(permutation, SlothPermutation::KeccakP(12));
+        assert_eq!(
+            SlothPermutation::from(KeccakF1600::<3>),
+            SlothPermutation::KeccakF(3)
+        );
+        let mut state = expected;
+        permutation.permute(&mut state);
+        KeccakP1600::<12>::inverse(&mut state);
+        assert_eq!(state, expected);
+    }
+
+    #[test]
+    fn invalid_params() {
+        let p = |x: u64| BigUint::from(x);
+        let keccak_f = SlothPermutation::KeccakF(1);
+        let variable = SlothMode::VariableTime;
+
+        assert_eq!(
+            SlothParams::new(p(1019), SlothPermutation::KeccakF(0), variable),
+            Err(SlothParamsError::InvalidRounds(0))
+        );
+        assert_eq!(
+            SlothParams::new(p(1019), SlothPermutation::KeccakF(25), variable),
+            Err(SlothParamsError::InvalidRounds(25))
+        );
+        assert_eq!(
+            SlothParams::new(p(1019), SlothPermutation::KeccakP(0), variable),
+            Err(SlothParamsError::InvalidRounds(0))
+        );
+        // 2^255 - 19 is congruent to 1 mod 4.
+        assert_eq!(
+            SlothParams::new((BigUint::one() << 255) - 19u8, keccak_f, variable),
+            Err(SlothParamsError::ModulusNotThreeModFour)
+        );
+        assert_eq!(
+            SlothParams::new(p(3), keccak_f, variable),
+            Err(SlothParamsError::ModulusTooSmall)
+        );
+        assert_eq!(
+            SlothParams::new(BigUint::one() << 1600, keccak_f, variable),
+            Err(SlothParamsError::ModulusTooLarge(1601))
+        );
+        assert_eq!(
+            SlothParams::new(p(1019), keccak_f, SlothMode::ConstantTime),
+            Err(SlothParamsError::ConstantTimeUnsupported)
+        );
+    }
+
+    // Sigma must be a permutation of the field for narrow primes.
+    #[test]
+    fn narrow_sigma_is_permutation() {
+        for modulus in [7u64, 1019] {
+            let params = SlothParams::new(
+                BigUint::from(modulus),
+                SlothPermutation::KeccakF(2),
+                SlothMode::VariableTime,
+            )
+            .unwrap();
+
+            let mut outputs: Vec<_> = (0..modulus)
+                .map(|x| params.sigma(BigUint::from(x)))
+                .collect();
+            for (x, y) in outputs.iter().enumerate() {
+                assert_eq!(params.sigma_inverse(y.clone()), BigUint::from(x));
+            }
+            outputs.sort();
+            assert_eq!(outputs, (0..modulus).map(BigUint::from).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn custom_params() {
+        let x = BigUint::from(11u64);
+
+        for permutation in [SlothPermutation::KeccakF(1), SlothPermutation::KeccakP(12)] {
+            let params = SlothParams::new(
+                SlothParams::p256().modulus().clone(),
+                permutation,
+                SlothMode::VariableTime,
+            )
+            .unwrap();
+
+            let witness = params.solve(x.clone(), 20).unwrap();
+            assert!(&witness < params.modulus());
+            assert_eq!(params.verify(x.clone(), witness.clone(), 20), Ok(true));
+            assert_eq!(params.verify(x.clone(), witness + 1u8, 20), Ok(false));
+        }
+
+        // The default parameters must match the free functions.
+        let params = SlothParams::default();
+        assert_eq!(params.solve(x.clone(), 3), solve(x, 3));
+    }
+
+    #[test]
+    fn proof_round_trip() {
+        for params in [SlothParams::p1600(), SlothParams::p256()] {
+            let proof = params.prove(BigUint::from(11u64), 5).unwrap();
+            assert!(proof.verify(&params));
+
+            let bytes = proof.to_bytes(&params).unwrap();
+            assert_eq!(bytes.len(), 2 * params.element_size() + 8 + 32);
+            assert_eq!(SlothProof::from_bytes(&bytes, &params), Ok(proof));
+        }
+        assert_eq!(SlothParams::p1600().element_size(), 200);
+    }
+
+    #[test]
+    fn strict_proof_decoding() {
+        let params = SlothParams::p256();
+        let proof = params.prove(BigUint::from(11u64), 5).unwrap();
+        let bytes = proof.to_bytes(&params).unwrap();
+
+        assert_eq!(
+            SlothProof::from_bytes(&bytes[1..], &params),
+            Err(SlothProofError::InvalidLength(bytes.len() - 1))
+        );
+        assert_eq!(
+            SlothProof::from_bytes(&bytes, &SlothParams::p1600()),
+            Err(SlothProofError::InvalidLength(bytes.len()))
+        );
+
+        // Witness + p encodes the same residue but is not canonical.
+        let mut non_canonical = proof.clone();
+        non_canonical.witness += params.modulus();
+        assert!(!non_canonical.verify(&params));
+        assert_eq!(
+            non_canonical.to_bytes(&params),
+            Err(SlothProofError::NonCanonical)
+        );
+        let mut encoded = bytes.clone();
+        encoded[32..64].copy_from_slice(&params.modulus().to_bytes_be());
+        assert_eq!(
+            SlothProof::from_bytes(&encoded, &params),
+            Err(SlothProofError::NonCanonical)
+        );
+
+        let other = SlothParams::new(
+            params.modulus().clone(),
+            SlothPermutation::KeccakP(12),
+            SlothMode::VariableTime,
+        )
+        .unwrap();
+        assert!(!proof.verify(&other));
+        assert_eq!(
+            SlothProof::from_bytes(&bytes, &other),
+            Err(SlothProofError::ParamsMismatch)
+        );
+
+        let mut tampered = proof;
+        tampered.delay += 1;
+        assert!(!tampered.verify(&params));
+    }
+
+    // `verify(solve(x))` holds for every x below p, and inputs or witnesses out of
+    // the domain are rejected with an error instead of being reduced.
+    #[test]
+    fn domain_checks() {
+        let params = SlothParams::new(
+            BigUint::from(1019u64),
+            SlothPermutation::KeccakF(1),
+            SlothMode::VariableTime,
+        )
+        .unwrap();
+        let p = params.modulus().clone();
+
+        for x in 0..1019u64 {
+            let x = BigUint::from(x);
+            let witness = params.solve(x.clone(), 3).unwrap();
+            assert!(witness < p);
+            assert_eq!(params.verify(x.clone(), witness.clone(), 3), Ok(true));
+            assert_eq!(
+                params.verify(x.clone() + &p, witness.clone(), 3),
+                Err(SlothError::InputOutOfRange)
+            );
+            assert_eq!(
+                params.verify(x.clone(), witness + &p, 3),
+                Err(SlothError::WitnessOutOfRange)
+            );
+            assert_eq!(
+                params.solve(x.clone() + &p, 3),
+                Err(SlothError::InputOutOfRange)
+            );
+            assert_eq!(params.prove(x + &p, 3), Err(SlothError::InputOutOfRange));
+        }
+
+        let too_large = [SEED.clone(), BigUint::one() << 1600, BigUint::one() << 2000];
+        for x in too_large.iter() {
+            for mode in [SlothMode::VariableTime, SlothMode::ConstantTime] {
+                assert_eq!(
+                    solve_with_mode(x.clone(), 1, mode),
+                    Err(SlothError::InputOutOfRange)
+                );
+                assert_eq!(
+                    verify_with_mode(BigUint::one(), x.clone(), 1, mode),
+                    Err(SlothError::WitnessOutOfRange)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn batch_verification() {
+        let items: Vec<_> = (0..12u64)
+            .map(|i| {
+                let x = BigUint::from(i);
+                let witness = solve(x.clone(), 2).unwrap();
+                (x, witness, 2)
+            })
+            .collect();
+        assert_eq!(verify_batch(&items), Ok(()));
+        assert_eq!(verify_batch(&[]), Ok(()));
+
+        let mut invalid = items.clone();
+        invalid[9].1 += 1u8;
+        invalid[4].2 = 3;
+        invalid[11].0 = SEED.clone();
+
+        // The lowest failing index is reported regardless of the number of threads.
+        for threads in [1, 2, 5, 16] {
+            assert_eq!(
+                SlothParams::default().verify_batch(&invalid, threads),
+                Err(BatchFailure {
+                    index: 4,
+                    error: None
+                })
+            );
+        }
+
+        invalid[4].2 = 2;
+        invalid[2].1 = SEED.clone();
+        assert_eq!(
+            verify_batch(&invalid),
+            Err(BatchFailure {
+                index: 2,
+                error: Some(SlothError::WitnessOutOfRange)
+            })
+        );
+    }
 }
@@ -0,0 +1,303 @@
+//! Keccak-f permutations of the narrower widths, Keccak-f[200], Keccak-f[400] and
+//! Keccak-f[800], and sponge constructions over them.
+//!
+//! These are not used by Keccak-prime itself. They are meant for testing firmware of
+//! lightweight devices and for experiments with a smaller state.
+
+use crate::{keccakf::RC, Hasher, Mode, Xof, WORDS};
+
+keccak_function!("`keccak-f[200, 18]`", keccakf200, u8, 18, RC);
+keccak_function!("`keccak-f[400, 20]`", keccakf400, u16, 20, RC);
+keccak_function!("`keccak-f[800, 22]`", keccakf800, u32, 22, RC);
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
+/// Lane of a narrow Keccak-f state. Implemented for `u8`, `u16` and `u32`, which select
+/// Keccak-f[200], Keccak-f[400] and Keccak-f[800] respectively.
+pub trait Lane: private::Sealed + Copy + Default {
+    /// Applies the Keccak-f permutation of the matching width to the state.
+    fn permute(state: &mut [Self; WORDS]);
+
+    /// XORs `byte` into the lane at little-endian byte position `index`.
+    fn xor_byte(&mut self, index: usize, byte: u8);
+
+    /// Returns the byte of the lane at little-endian byte position `index`.
+    fn byte(self, index: usize) -> u8;
+}
+
+macro_rules! impl_lane {
+    ($lane: ty, $permutation: ident) => {
+        impl Lane for $lane {
+            fn permute(state: &mut [Self; WORDS]) {
+                $permutation(state);
+            }
+
+            #[allow(clippy::unnecessary_cast)]
+            fn xor_byte(&mut self, index: usize, byte: u8) {
+                *self ^= (byte as $lane) << (8 * index);
+            }
+
+            fn byte(self, index: usize) -> u8 {
+                (self >> (8 * index)) as u8
+            }
+        }
+    };
+}
+
+impl_lane!(u8, keccakf200);
+impl_lane!(u16, keccakf400);
+impl_lane!(u32, keccakf800);
+
+/// Sponge over the narrow Keccak-f permutation selected by the lane type `L`.
+///
+/// Padding is `pad10*1` applied after the domain separation byte `delim`, like in
+/// [`Keccak`](crate::Keccak) (`0x01`) or [`Shake`](crate::Shake) (`0x1f`).
+///
+/// # Example
+///
+/// ```
+/// # use keccak_prime::{Hasher, SmallKeccak};
+/// // Keccak-f[800] with a 256-bit rate and a 544-bit capacity.
+/// let mut keccak = SmallKeccak::<u32>::new(32, 0x01);
+/// let mut output = [0u8; 32];
+/// keccak.update(b"hello world");
+/// keccak.finalize(&mut output);
+/// ```
+#[derive(Clone)]
+pub struct SmallKeccak<L> {
+    state: [L; WORDS],
+    offset: usize,
+    rate: usize,
+    delim: u8,
+    mode: Mode,
+}
+
+impl<L: Lane> SmallKeccak<L> {
+    /// Width of the state, in bytes.
+    pub const STATE_SIZE: usize = WORDS * core::mem::size_of::<L>();
+
+    /// Creates a new sponge with a rate in bytes and a domain separation byte.
+    ///
+    /// # Panics
+    ///
+    /// If the rate is zero, or leaves no room for the capacity.
+    pub fn new(rate: usize, delim: u8) -> Self {
+        assert!(rate != 0, "rate cannot be equal 0");
+        assert!(
+            rate < Self::STATE_SIZE,
+            "rate must be below the state size of {} bytes",
+            Self::STATE_SIZE
+        );
+        SmallKeccak {
+            state: [L::default(); WORDS],
+            offset: 0,
+            rate,
+            delim,
+            mode: Mode::Absorbing,
+        }
+    }
+
+    /// Rate of the sponge, in bytes.
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    /// Capacity of the sponge, in bits.
+    pub fn capacity(&self) -> usize {
+        (Self::STATE_SIZE - self.rate) * 8
+    }
+
+    fn xor_byte(&mut self, position: usize, byte: u8) {
+        let lane_size = core::mem::size_of::<L>();
+        self.state[position / lane_size].xor_byte(position % lane_size, byte);
+    }
+
+    fn byte(&self, position: usize) -> u8 {
+        let lane_size = core::mem::size_of::<L>();
+        self.state[position / lane_size].byte(position % lane_size)
+    }
+
+    fn permute(&mut self) {
+        L::permute(&mut self.state);
+        self.offset = 0;
+    }
+}
+
+impl<L: Lane> Hasher for SmallKeccak<L> {
+    fn update(&mut self, input: &[u8]) {
+        if let Mode::Squeezing = self.mode {
+            self.mode = Mode::Absorbing;
+            self.permute();
+        }
+
+        for byte in input {
+            self.xor_byte(self.offset, *byte);
+            self.offset += 1;
+            if self.offset == self.rate {
+                self.permute();
+            }
+        }
+    }
+
+    fn finalize(mut self, output: &mut [u8]) {
+        self.squeeze(output);
+    }
+}
+
+impl<L: Lane> Xof for SmallKeccak<L> {
+    fn squeeze(&mut self, output: &mut [u8]) {
+        if let Mode::Absorbing = self.mode {
+            self.mode = Mode::Squeezing;
+            self.xor_byte(self.offset, self.delim);
+            self.xor_byte(self.rate - 1, 0x80);
+            self.permute();
+        }
+
+        for byte in output {
+            if self.offset == self.rate {
+                self.permute();
+            }
+            *byte = self.byte(self.offset);
+            self.offset += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Known-answer vectors: the all-zero state permuted once and twice, in the format of
+    // the Keccak team's `KeccakF-<b>-IntermediateValues.txt` files.
+
+    #[test]
+    fn keccakf200_zero_state() {
+        let mut state = [0u8; WORDS];
+        keccakf200(&mut state);
+        assert_eq!(
+            state,
+            [
+                0x3c, 0x28, 0x26, 0x84, 0x1c, 0xb3, 0x5c, 0x17, 0x1e, 0xaa, 0xe9, 0xb8, 0x11, 0x13,
+                0x4c, 0xea, 0xa3, 0x85, 0x2c, 0x69, 0xd2, 0xc5, 0xab, 0xaf, 0xea,
+            ]
+        );
+        keccakf200(&mut state);
+        assert_eq!(
+            state,
+            [
+                0x1b, 0xef, 0x68, 0x94, 0x92, 0xa8, 0xa5, 0x43, 0xa5, 0x99, 0x9f, 0xdb, 0x83, 0x4e,
+                0x31, 0x66, 0xa1, 0x4b, 0xe8, 0x27, 0xd9, 0x50, 0x40, 0x47, 0x9e,
+            ]
+        );
+    }
+
+    #[test]
+    fn keccakf400_zero_state() {
+        let mut state = [0u16; WORDS];
+        keccakf400(&mut state);
+        assert_eq!(
+            state,
+            [
+                0x09f5, 0x40ac, 0x0fa9, 0x14f5, 0xe89f, 0xeca0, 0x5bd1, 0x7870, 0xeff0, 0xbf8f,
+                0x0337, 0x6052, 0xdc75, 0x0ec9, 0xe776, 0x5246, 0x59a1, 0x5d81, 0x6d95, 0x6e14,
+                0x633e, 0x58ee, 0x71ff, 0x714c, 0xb38e,
+            ]
+        );
+        keccakf400(&mut state);
+        assert_eq!(
+            state,
+            [
+                0xe537, 0xd5d6, 0xdbe7, 0xaaf3, 0x9bc7, 0xca7d, 0x86b2, 0xfdec, 0x692c, 0x4e5b,
+                0x67b1, 0x15ad, 0xa7f7, 0xa66f, 0x67ff, 0x3f8a, 0x2f99, 0xe2c2, 0x656b, 0x5f31,
+                0x5ba6, 0xca29, 0xc224, 0xb85c, 0x097c,
+            ]
+        );
+    }
+
+    #[test]
+    fn keccakf800_zero_state() {
+        let mut state = [0u32; WORDS];
+        keccakf800(&mut state);
+        assert_eq!(
+            state,
+            [
+                0xe531d45d, 0xf404c6fb, 0x23a0bf99, 0xf1f8452f, 0x51ffd042, 0xe539f578, 0xf00b80a7,
+                0xaf973664, 0xbf5af34c, 0x227a2424, 0x88172715, 0x9f685884, 0xb15cd054, 0x1bf4fc0e,
+                0x6166fa91, 0x1a9e599a, 0xa3970a1f, 0xab659687, 0xafab8d68, 0xe74b1015, 0x34001a98,
+                0x4119eff3, 0x930a0e76, 0x87b28070, 0x11efe996,
+            ]
+        );
+        keccakf800(&mut state);
+        assert_eq!(
+            state,
+            [
+                0x75bf2d0d, 0x9b610e89, 0xc826af40, 0x64cd84ab, 0xf905bdd6, 0xbc832835, 0x5f8001b9,
+                0x15662cce, 0x8e38c95e, 0x701fe543, 0x1b544380, 0x89acdeff, 0x51edb5de, 0x0e9702d9,
+                0x6c19aa16, 0xa2913eee, 0x60754e9a, 0x9819063c, 0xf4709254, 0xd09f9084, 0x772da259,
+                0x1db35df7, 0x5aa60162, 0x358825d5, 0xb3783bab,
+            ]
+        );
+    }
+
+    fn hash<L: Lane>(rate: usize, input: &[u8]) -> String {
+        let mut keccak = SmallKeccak::<L>::new(rate, 0x01);
+        let mut output = [0u8; 64];
+        keccak.update(input);
+        keccak.finalize(&mut output);
+        hex::encode(output)
+    }
+
+    #[test]
+    fn sponges() {
+        // Regression values rather than published vectors: they were produced by an
+        // independent implementation of the FIPS 202 sponge, validated against the
+        // Keccak-f[1600] known answers, and pin the padding and the multi-block absorption.
+        let long_input: Vec<u8> = (0..200).map(|i| i as u8).collect();
+
+        assert_eq!(
+            hash::<u8>(18, b"abc"),
+            "f315b6d472f24253f96f3528027b1d2224f12a0f532c6e8e62fe8f045a2f4cfd\
+             f3e82b450a43c72ddc393e5c688974099756d11ce77cf6654731a4825819395b"
+        );
+        assert_eq!(
+            hash::<u8>(18, &long_input),
+            "11f56621fd9c7a2882631ab8a2ff7f3081efb77db9c0f2ff9f16bd1a3276a68c\
+             0b16ddff5061388c03172b667ced9a6b19e576ab70f5806e0ed5aa3bcd3ba966"
+        );
+        assert_eq!(
+            hash::<u16>(34, b"abc"),
+            "a5fbee90f5c0b80dd544f171a2928f48a81d72dbecce95e1b1dcfe658a4d5bc2\
+             4d90df18e27993cb2563c9edd75f7997f9ff4167e4a1ec70ab58500b97256857"
+        );
+        assert_eq!(
+            hash::<u16>(34, &long_input),
+            "7bdc4ddbc6bbba416db2fc5583160cfb8a1776e29a48ded5e1d36308ff975b24\
+             f0a4c334a867826c7dd98884af72ebc27672961dec22036ac530fe8b1203fa4b"
+        );
+        assert_eq!(
+            hash::<u32>(64, b"abc"),
+            "25d746c70caf11ea75b1db46926f279277658d410490e08347dedb105c854864\
+             03688318ebbadd25beded67b92e8c98210304cfeb67949868bbc3f92a55e4737"
+        );
+        assert_eq!(
+            hash::<u32>(64, &long_input),
+            "6e665f8d1f600d11dfd24e496487323fad31d144d748124410f917a42d575b6c\
+             5f5e62c48f0f58939c3110ac8c1769ca877c8784ed8f19f228d6394458678e31"
+        );
+
+        // Absorbing in pieces and squeezing in pieces gives the same output.
+        let mut keccak = SmallKeccak::<u16>::new(34, 0x01);
+        keccak.update(&long_input[..33]);
+        keccak.update(&long_input[33..]);
+        let mut output = [0u8; 64];
+        keccak.squeeze(&mut output[..35]);
+        keccak.squeeze(&mut output[35..]);
+        assert_eq!(hex::encode(output), hash::<u16>(34, &long_input));
+    }
+}
@@ -0,0 +1,358 @@
+//! Verifiable delay functions used in the Keccak-prime chain.
+//!
+//! Two VDFs are available behind the common [`Vdf`] trait:
+//! - Sloth ([`SlothParams`]), whose verification is only a constant factor faster than the
+//!   evaluation, but which needs no proof besides the output.
+//! - Wesolowski's VDF over an RSA group ([`Wesolowski`]), which produces a short proof that
+//!   is verified with a couple of exponentiations, independently of the delay.
+//!
+//! [`VdfKind`] selects one of them in [`PrimeParams`](crate::params::PrimeParams).
+
+use std::fmt::Debug;
+
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use crate::{keccak::Keccak, sloth::SlothParams, Hasher, Xof};
+
+/// RSA-2048 modulus from the RSA Factoring Challenge. Its factorization is unknown.
+const RSA_2048: &str = "\
+    c7970ceedcc3b0754490201a7aa613cd73911081c790f5f1a8726f463550bb5b7ff0db8e1ea1189ec72f93d165\
+    0011bd721aeeacc2acde32a04107f0648c2813a31f5b0b7765ff8b44b4b6ffc93384b646eb09c7cf5e8592d40e\
+    a33c80039f35b4f14a04b51f7bfd781be4d1673164ba8eb991c2c4d730bbbe35f592bdef524af7e8daefd26c66\
+    fc02c479af89d64d373f442709439de66ceb955f3ea37d5159f6135809f85334b5cb1813addc80cd05609f10ac\
+    6a95ad65872c909525bdad32bc729592642920f24c61dc5b3c3b7923e56b16a4d9d373d8721f24a3fc0f1b3131\
+    f55615172866bccc30f95054c824e733a5eb6817f7bc16399d48c6361cc7e5";
+
+/// Size of the Wesolowski challenge primes, in bits.
+const CHALLENGE_BITS: u64 = 128;
+
+/// Bases used in the Miller-Rabin test for challenge primes.
+const MILLER_RABIN_BASES: [u32; 16] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+
+lazy_static! {
+    static ref SLOTH: SlothParams = SlothParams::default();
+    static ref WESOLOWSKI: Wesolowski = Wesolowski::rsa_2048();
+}
+
+/// Verifiable delay function.
+pub trait Vdf {
+    /// Proof which accompanies the output.
+    type Proof: Debug + Clone + PartialEq + Eq;
+
+    /// Evaluates the VDF for `input` with the difficulty `delay`.
+    ///
+    /// ## Returns
+    /// - The output together with a proof of its correctness.
+    fn eval(&self, input: &BigUint, delay: u64) -> (BigUint, Self::Proof);
+
+    /// Checks that `output` is the VDF output for `input` and `delay`.
+    fn verify(&self, input: &BigUint, delay: u64, output: &BigUint, proof: &Self::Proof) -> bool;
+}
+
+/// Sloth as a VDF. The output is the witness itself, so no extra proof is required.
+///
+/// Inputs must be below p. They are not reduced, so that an input and the same input
+/// plus p can't share a witness.
+///
+/// # Panics
+///
+/// [`Vdf::eval`] panics if the input is not below p, while [`Vdf::verify`] rejects it.
+impl Vdf for SlothParams {
+    type Proof = ();
+
+    fn eval(&self, input: &BigUint, delay: u64) -> (BigUint, ()) {
+        let output = self
+            .solve(input.clone(), delay)
+            .expect("input must be below the Sloth modulus");
+        (output, ())
+    }
+
+    fn verify(&self, input: &BigUint, delay: u64, output: &BigUint, _proof: &()) -> bool {
+        self.verify(input.clone(), output.clone(), delay)
+            .unwrap_or(false)
+    }
+}
+
+/// Wesolowski's VDF over the RSA group of a fixed modulus N.
+///
+/// The input is hashed into the group as `x`, and the output is `y = x ^ (2 ^ delay) mod N`.
+/// The proof is `pi = x ^ floor(2 ^ delay / l)`, where `l` is a 128-bit prime derived from
+/// `x`, `y` and `delay`, and it is checked as `pi ^ l * x ^ (2 ^ delay mod l) = y`.
+///
+/// `-1` is a known element of order two, so `(-y, -pi)` would verify as well. Both `y` and
+/// `pi` are therefore taken in the quotient group by `{1, -1}`, represented by
+/// `min(v, N - v)`, and other representatives are rejected.
+///
+/// See "Efficient verifiable delay functions" by B. Wesolowski.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wesolowski {
+    modulus: BigUint,
+}
+
+/// Proof of a [`Wesolowski`] evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WesolowskiProof {
+    /// `x ^ floor(2 ^ delay / l) mod N`
+    pub pi: BigUint,
+}
+
+impl Wesolowski {
+    /// Uses the RSA-2048 challenge modulus, which is meant for testing: a production setup
+    /// requires a modulus generated in a trusted ceremony.
+    pub fn rsa_2048() -> Wesolowski {
+        Wesolowski {
+            modulus: BigUint::parse_bytes(RSA_2048.as_bytes(), 16).expect("valid modulus"),
+        }
+    }
+
+    /// RSA modulus of the group.
+    pub fn modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    /// Canonical representative of `{v, N - v}`.
+    fn normalize(&self, v: BigUint) -> BigUint {
+        let negated = &self.modulus - &v;
+        v.min(negated)
+    }
+
+    /// Hashes an input into the group.
+    fn hash_to_group(&self, input: &BigUint) -> BigUint {
+        let mut keccak = Keccak::v256();
+        keccak.update(b"wesolowski-input");
+        keccak.update(&input.to_bytes_be());
+
+        // Extra 128 bits make the bias of the modular reduction negligible.
+        let mut bytes = vec![0u8; (self.modulus.bits() as usize + 128).div_ceil(8)];
+        keccak.into_xof_with_penalty(0).squeeze(&mut bytes);
+        BigUint::from_bytes_be(&bytes) % &self.modulus
+    }
+
+    /// Derives the challenge prime `l` from the statement.
+    fn hash_to_prime(&self, x: &BigUint, y: &BigUint, delay: u64) -> BigUint {
+        let mut counter = 0u64;
+        loop {
+            let mut keccak = Keccak::v256();
+            keccak.update(b"wesolowski-prime");
+            keccak.update(&x.to_bytes_be());
+            keccak.update(&y.to_bytes_be());
+            keccak.update(&delay.to_be_bytes());
+            keccak.update(&counter.to_be_bytes());
+
+            let mut bytes = [0u8; CHALLENGE_BITS as usize / 8];
+            keccak.into_xof_with_penalty(0).squeeze(&mut bytes);
+            // Fix the top bit, so that all challenges have the same size, and make it odd.
+            let candidate = BigUint::from_bytes_be(&bytes)
+                | (BigUint::one() << (CHALLENGE_BITS - 1))
+                | BigUint::one();
+
+            if is_probable_prime(&candidate) {
+                return candidate;
+            }
+            counter += 1;
+        }
+    }
+}
+
+impl Vdf for Wesolowski {
+    type Proof = WesolowskiProof;
+
+    fn eval(&self, input: &BigUint, delay: u64) -> (BigUint, WesolowskiProof) {
+        let x = self.hash_to_group(input);
+
+        let mut y = x.clone();
+        for _ in 0..delay {
+            y = &y * &y % &self.modulus;
+        }
+        let y = self.normalize(y);
+
+        // Long division of 2 ^ delay by l, accumulating the quotient bits in the exponent.
+        let l = self.hash_to_prime(&x, &y, delay);
+        let mut pi = BigUint::one();
+        let mut remainder = BigUint::one();
+        for _ in 0..delay {
+            remainder <<= 1;
+            pi = &pi * &pi % &self.modulus;
+            if remainder >= l {
+                remainder -= &l;
+                pi = pi * &x % &self.modulus;
+            }
+        }
+
+        let pi = self.normalize(pi);
+        (y, WesolowskiProof { pi })
+    }
+
+    fn verify(
+        &self,
+        input: &BigUint,
+        delay: u64,
+        output: &BigUint,
+        proof: &WesolowskiProof,
+    ) -> bool {
+        // Reject non-canonical encodings of the group elements, including the negated ones.
+        if *output >= self.modulus || proof.pi >= self.modulus || proof.pi.is_zero() {
+            return false;
+        }
+        if self.normalize(output.clone()) != *output || self.normalize(proof.pi.clone()) != proof.pi
+        {
+            return false;
+        }
+
+        let x = self.hash_to_group(input);
+        let l = self.hash_to_prime(&x, output, delay);
+        let r = BigUint::from(2u8).modpow(&BigUint::from(delay), &l);
+
+        // `l` is odd, so the sign of `pi` carries over, and the result is compared up to sign.
+        let y = proof.pi.modpow(&l, &self.modulus) * x.modpow(&r, &self.modulus) % &self.modulus;
+        self.normalize(y) == *output
+    }
+}
+
+/// Miller-Rabin test with fixed bases, so that all nodes agree on the challenge primes.
+fn is_probable_prime(n: &BigUint) -> bool {
+    let one = BigUint::one();
+    let n_minus_one = n - &one;
+    let s = n_minus_one.trailing_zeros().expect("n is odd and above 1");
+    let d = &n_minus_one >> s;
+
+    'bases: for base in MILLER_RABIN_BASES {
+        let base = BigUint::from(base);
+        if base >= n_minus_one {
+            continue;
+        }
+
+        let mut x = base.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// VDF used in the Keccak-prime chain.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VdfKind {
+    /// Sloth with the default [`SlothParams`].
+    #[default]
+    Sloth,
+    /// Wesolowski's VDF over the [`Wesolowski::rsa_2048`] test modulus.
+    Wesolowski,
+}
+
+/// Proof of a [`VdfKind`] evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VdfProof {
+    /// Sloth requires no extra proof.
+    Sloth,
+    /// Proof of a Wesolowski evaluation.
+    Wesolowski(WesolowskiProof),
+}
+
+impl Vdf for VdfKind {
+    type Proof = VdfProof;
+
+    fn eval(&self, input: &BigUint, delay: u64) -> (BigUint, VdfProof) {
+        match self {
+            VdfKind::Sloth => {
+                let (output, ()) = SLOTH.eval(input, delay);
+                (output, VdfProof::Sloth)
+            }
+            VdfKind::Wesolowski => {
+                let (output, proof) = WESOLOWSKI.eval(input, delay);
+                (output, VdfProof::Wesolowski(proof))
+            }
+        }
+    }
+
+    fn verify(&self, input: &BigUint, delay: u64, output: &BigUint, proof: &VdfProof) -> bool {
+        match (self, proof) {
+            (VdfKind::Sloth, VdfProof::Sloth) => Vdf::verify(&*SLOTH, input, delay, output, &()),
+            (VdfKind::Wesolowski, VdfProof::Wesolowski(proof)) => {
+                WESOLOWSKI.verify(input, delay, output, proof)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modulus() {
+        let modulus = Wesolowski::rsa_2048().modulus().clone();
+        assert_eq!(modulus.bits(), 2048);
+        assert_eq!(modulus.to_str_radix(10).len(), 617);
+        assert!(!is_probable_prime(&modulus));
+        assert!(is_probable_prime(&((BigUint::one() << 127) - 1u8)));
+    }
+
+    #[test]
+    fn wesolowski_proofs() {
+        let vdf = Wesolowski::rsa_2048();
+        let input = BigUint::from_bytes_be(&[0xa5; 136]);
+
+        let (output, proof) = vdf.eval(&input, 1000);
+        assert!(vdf.verify(&input, 1000, &output, &proof));
+
+        // Tampering with any part of the statement must be detected.
+        assert!(!vdf.verify(&(&input + 1u8), 1000, &output, &proof));
+        assert!(!vdf.verify(&input, 999, &output, &proof));
+        assert!(!vdf.verify(&input, 1000, &(&output + 1u8), &proof));
+        let tampered = WesolowskiProof {
+            pi: &proof.pi + 1u8,
+        };
+        assert!(!vdf.verify(&input, 1000, &output, &tampered));
+        let non_canonical = WesolowskiProof {
+            pi: &proof.pi + vdf.modulus(),
+        };
+        assert!(!vdf.verify(&input, 1000, &output, &non_canonical));
+
+        // Negating both the output and the proof keeps the verification equation, so only
+        // the smaller representatives are accepted.
+        let negated_output = vdf.modulus() - &output;
+        let negated_proof = WesolowskiProof {
+            pi: vdf.modulus() - &proof.pi,
+        };
+        assert_ne!(negated_output, output);
+        assert!(!vdf.verify(&input, 1000, &negated_output, &negated_proof));
+        assert!(!vdf.verify(&input, 1000, &output, &negated_proof));
+        assert!(!vdf.verify(&input, 1000, &negated_output, &proof));
+    }
+
+    #[test]
+    fn vdf_kinds() {
+        let input = BigUint::from(11u64);
+
+        for kind in [VdfKind::Sloth, VdfKind::Wesolowski] {
+            let (output, proof) = kind.eval(&input, 10);
+            assert!(kind.verify(&input, 10, &output, &proof));
+            assert!(!kind.verify(&input, 11, &output, &proof));
+        }
+
+        let (output, proof) = VdfKind::Sloth.eval(&input, 10);
+        assert!(!VdfKind::Wesolowski.verify(&input, 10, &output, &proof));
+        assert_eq!(output, crate::sloth::solve(input.clone(), 10).unwrap());
+
+        // Sloth inputs are not reduced modulo p.
+        let unreduced = &input + SLOTH.modulus();
+        assert!(!VdfKind::Sloth.verify(&unreduced, 10, &output, &proof));
+    }
+
+    #[test]
+    #[should_panic(expected = "input must be below the Sloth modulus")]
+    fn sloth_input_out_of_range() {
+        SLOTH.eval(SLOTH.modulus(), 10);
+    }
+}
@@ -0,0 +1,253 @@
+//! Versions of the Keccak-prime algorithm and their activation schedule.
+//!
+//! Any change to the expansion function, the VDF or the final Keccak changes every
+//! Keccak-prime output, and therefore consensus. Each [`PrimeVersion`] pins these components,
+//! while the difficulty parameters (`penalty`, `delay` and `vdf_iterations`) are supplied per
+//! evaluation, as in [`prime`](crate::prime::prime). A [`VersionSchedule`] selects the version
+//! by block height.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::{
+    expansion::{INPUT_HASH_SIZE, NONCE_SIZE},
+    params::{ExpansionKdf, PrimeParams, PrimeParamsBuilder, SpongeVersion},
+    prime::{prime_with_params, KeccakPrimeError},
+    vdf::VdfKind,
+};
+
+/// Version of the Keccak-prime algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PrimeVersion {
+    /// The original algorithm used by the Zenotta main network: XOR key derivation,
+    /// Sloth over p = 2^1600 - 2273 and the [`SpongeVersion::Legacy`] final Keccak.
+    V0,
+    /// [`PrimeVersion::V0`] with the [`SpongeVersion::V1`] final Keccak.
+    V1,
+    /// [`PrimeVersion::V1`] with the [`ExpansionKdf::CShake256`] key derivation.
+    /// Requires the `cshake` feature.
+    #[cfg(feature = "cshake")]
+    V2,
+}
+
+impl PrimeVersion {
+    /// Creates a [`PrimeParamsBuilder`] with the components pinned by this version.
+    fn builder(&self) -> PrimeParamsBuilder {
+        let (kdf, sponge) = match self {
+            PrimeVersion::V0 => (ExpansionKdf::Xor, SpongeVersion::Legacy),
+            PrimeVersion::V1 => (ExpansionKdf::Xor, SpongeVersion::V1),
+            #[cfg(feature = "cshake")]
+            PrimeVersion::V2 => (ExpansionKdf::CShake256, SpongeVersion::V1),
+        };
+        PrimeParams::builder()
+            .expansion_size(136)
+            .output_len(INPUT_HASH_SIZE)
+            .kdf(kdf)
+            .vdf(VdfKind::Sloth)
+            .sponge(sponge)
+    }
+
+    /// Parameters of this version with the provided difficulty.
+    pub fn params(
+        &self,
+        penalty: usize,
+        delay: u64,
+        vdf_iterations: usize,
+    ) -> Result<PrimeParams, KeccakPrimeError> {
+        Ok(self
+            .builder()
+            .penalty(penalty)
+            .delay(delay)
+            .vdf_iterations(vdf_iterations)
+            .build()?)
+    }
+
+    /// Keccak-prime function of this version.
+    ///
+    /// Takes the same arguments as [`prime`](crate::prime::prime), which is equal to
+    /// [`PrimeVersion::V0`].
+    pub fn prime(
+        &self,
+        prev_hash: [u8; INPUT_HASH_SIZE],
+        root_hash: [u8; INPUT_HASH_SIZE],
+        nonce: [u8; NONCE_SIZE],
+        penalty: usize,
+        delay: u64,
+        vdf_iterations: usize,
+    ) -> Result<[u8; INPUT_HASH_SIZE], KeccakPrimeError> {
+        let params = self.params(penalty, delay, vdf_iterations)?;
+
+        let mut output = [0u8; INPUT_HASH_SIZE];
+        output.copy_from_slice(&prime_with_params(prev_hash, root_hash, nonce, &params)?);
+        Ok(output)
+    }
+}
+
+/// Maps block heights to Keccak-prime versions.
+///
+/// # Example
+///
+/// ```
+/// # use keccak_prime::version::{PrimeVersion, VersionSchedule};
+/// let schedule = VersionSchedule::builder(PrimeVersion::V0)
+///     .activate(1_000, PrimeVersion::V1)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(schedule.version_at(999), PrimeVersion::V0);
+/// assert_eq!(schedule.version_at(1_000), PrimeVersion::V1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionSchedule {
+    /// Activation heights in increasing order, starting with the genesis version at height 0.
+    activations: Vec<(u64, PrimeVersion)>,
+}
+
+impl VersionSchedule {
+    /// Schedule of the Zenotta main network, which uses [`PrimeVersion::V0`] at all heights.
+    pub fn zenotta_mainnet() -> VersionSchedule {
+        VersionSchedule {
+            activations: vec![(0, PrimeVersion::V0)],
+        }
+    }
+
+    /// Creates a new [`VersionScheduleBuilder`] starting with the `genesis` version at height 0.
+    pub fn builder(genesis: PrimeVersion) -> VersionScheduleBuilder {
+        VersionScheduleBuilder {
+            activations: vec![(0, genesis)],
+        }
+    }
+
+    /// Returns the version active at the block `height`.
+    pub fn version_at(&self, height: u64) -> PrimeVersion {
+        let index = self
+            .activations
+            .partition_point(|(activation, _)| *activation <= height);
+        self.activations[index - 1].1
+    }
+
+    /// Activation heights with their versions, in increasing order.
+    pub fn activations(&self) -> &[(u64, PrimeVersion)] {
+        &self.activations
+    }
+}
+
+/// Builder for [`VersionSchedule`].
+#[derive(Debug, Clone)]
+pub struct VersionScheduleBuilder {
+    activations: Vec<(u64, PrimeVersion)>,
+}
+
+impl VersionScheduleBuilder {
+    /// Activates `version` starting from the block `height`.
+    pub fn activate(mut self, height: u64, version: PrimeVersion) -> Self {
+        self.activations.push((height, version));
+        self
+    }
+
+    /// Validates the activation heights and builds [`VersionSchedule`].
+    pub fn build(self) -> Result<VersionSchedule, VersionScheduleError> {
+        for pair in self.activations.windows(2) {
+            if pair[1].0 <= pair[0].0 {
+                return Err(VersionScheduleError::HeightNotIncreasing(pair[1].0));
+            }
+        }
+        Ok(VersionSchedule {
+            activations: self.activations,
+        })
+    }
+}
+
+/// Invalid [`VersionSchedule`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionScheduleError {
+    /// The activation height isn't above the previous activation height.
+    HeightNotIncreasing(u64),
+}
+
+impl fmt::Display for VersionScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionScheduleError::HeightNotIncreasing(height) => write!(
+                f,
+                "activation height {} must be above the previous activation height",
+                height
+            ),
+        }
+    }
+}
+
+impl Error for VersionScheduleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::prime;
+
+    /// Outputs frozen for each version. Any change to them breaks consensus, so it has to
+    /// be introduced as a new version instead.
+    #[test]
+    fn golden_outputs() {
+        let (prev_hash, root_hash, nonce) = ([1u8; 32], [2u8; 32], [3u8; 8]);
+
+        let v0 = PrimeVersion::V0
+            .prime(prev_hash, root_hash, nonce, 100, 10, 2)
+            .unwrap();
+        assert_eq!(
+            hex::encode(v0),
+            "e46ec0def5007ca82aa12f4a5745e2a7e94b2863f2abc0d4d213d773d7a5ddd2"
+        );
+        assert_eq!(v0, prime(prev_hash, root_hash, nonce, 100, 10, 2).unwrap());
+
+        let v1 = PrimeVersion::V1
+            .prime(prev_hash, root_hash, nonce, 100, 10, 2)
+            .unwrap();
+        assert_eq!(
+            hex::encode(v1),
+            "da1e7fe3547ddbdc52f374805fe6194142d9791e33f092c1ce82e0be3f4772fb"
+        );
+
+        #[cfg(feature = "cshake")]
+        {
+            let v2 = PrimeVersion::V2
+                .prime(prev_hash, root_hash, nonce, 100, 10, 2)
+                .unwrap();
+            assert_eq!(
+                hex::encode(v2),
+                "6100e26832a86bdc1d75aec2e00dc92f05415cc4a20f489efce430b3e7a86a20"
+            );
+        }
+    }
+
+    #[test]
+    fn schedule() {
+        let schedule = VersionSchedule::builder(PrimeVersion::V0)
+            .activate(100, PrimeVersion::V1)
+            .activate(200, PrimeVersion::V0)
+            .build()
+            .unwrap();
+        assert_eq!(schedule.version_at(0), PrimeVersion::V0);
+        assert_eq!(schedule.version_at(99), PrimeVersion::V0);
+        assert_eq!(schedule.version_at(100), PrimeVersion::V1);
+        assert_eq!(schedule.version_at(199), PrimeVersion::V1);
+        assert_eq!(schedule.version_at(u64::MAX), PrimeVersion::V0);
+        assert_eq!(
+            VersionSchedule::zenotta_mainnet().version_at(u64::MAX),
+            PrimeVersion::V0
+        );
+
+        assert_eq!(
+            VersionSchedule::builder(PrimeVersion::V0)
+                .activate(0, PrimeVersion::V1)
+                .build(),
+            Err(VersionScheduleError::HeightNotIncreasing(0))
+        );
+        assert_eq!(
+            VersionSchedule::builder(PrimeVersion::V0)
+                .activate(100, PrimeVersion::V1)
+                .activate(100, PrimeVersion::V0)
+                .build(),
+            Err(VersionScheduleError::HeightNotIncreasing(100))
+        );
+    }
+}